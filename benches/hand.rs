@@ -0,0 +1,113 @@
+//! Throughput of dealing and evaluating hands, and of whole simulated rounds.
+//! The incremental `Hand` is compared with a naive hand that recomputes its totals
+//! from a `Vec` of cards on every query, as hands used to.
+//! Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use blackjack_rust::chart::Chart;
+use blackjack_rust::deck::{Card, Deck, Rank};
+use blackjack_rust::hand::{Hand, HandValue};
+use blackjack_rust::rules::TableRules;
+use blackjack_rust::simulation;
+
+const HANDS: u32 = 5_000_000;
+const ROUNDS: u64 = 500_000;
+
+/// The reference hand, allocating its cards and recomputing its totals every time.
+struct NaiveHand {
+    cards: Vec<Card>,
+}
+
+impl NaiveHand {
+    fn new(deck: &mut Deck) -> NaiveHand {
+        NaiveHand {
+            cards: deck.get_initial_cards().to_vec(),
+        }
+    }
+
+    fn value(&self) -> HandValue {
+        let hard_total: u8 = self
+            .cards
+            .iter()
+            .map(|card| match card.rank() {
+                Rank::Ace => 1,
+                _ => card.value(),
+            })
+            .sum();
+        let has_ace: bool = self.cards.iter().any(|card| card.rank() == Rank::Ace);
+        if has_ace && hard_total + 10 <= 21 {
+            HandValue::Soft(hard_total + 10)
+        } else {
+            HandValue::Hard(hard_total)
+        }
+    }
+
+    fn is_bust(&self) -> bool {
+        self.value().is_bust()
+    }
+}
+
+fn main() {
+    let rules = TableRules::default();
+
+    let mut deck = Deck::from_seed(rules.n_decks, 0);
+    let mut naive_busts: u32 = 0;
+    let start = Instant::now();
+    for _ in 0..HANDS {
+        let mut hand = NaiveHand::new(&mut deck);
+        while rules.dealer_hits(hand.value()) {
+            hand.cards.push(deck.deal_card());
+        }
+        if hand.is_bust() {
+            naive_busts += 1;
+        }
+        deck.discard(&hand.cards);
+        if deck.needs_shuffle() {
+            deck.shuffle();
+        }
+    }
+    let naive: Duration = start.elapsed();
+    report("Dealer hands (naive)", u64::from(HANDS), naive);
+
+    let mut deck = Deck::from_seed(rules.n_decks, 0);
+    let mut busts: u32 = 0;
+    let start = Instant::now();
+    for _ in 0..HANDS {
+        let mut hand = Hand::new(&mut deck);
+        while rules.dealer_hits(hand.value()) {
+            hand.deal_card(&mut deck);
+        }
+        if hand.is_bust() {
+            busts += 1;
+        }
+        deck.discard(hand.cards());
+        if deck.needs_shuffle() {
+            deck.shuffle();
+        }
+    }
+    let incremental: Duration = start.elapsed();
+    report("Dealer hands", u64::from(HANDS), incremental);
+    assert_eq!(busts, naive_busts, "Both hands must deal the same cards");
+    println!(
+        "({} busts, {:.2}x the naive throughput)",
+        busts,
+        naive.as_secs_f64() / incremental.as_secs_f64()
+    );
+
+    let chart = Chart::basic(&rules);
+    let start = Instant::now();
+    let stats = simulation::simulate(&rules, &chart, ROUNDS, 0, 0.75);
+    report("Simulated rounds", ROUNDS, start.elapsed());
+    println!("({:.3}% house edge)", stats.house_edge() * 100.0);
+}
+
+fn report(name: &str, count: u64, elapsed: Duration) {
+    println!(
+        "{}: {} in {:.2?}, {:.0} per second",
+        name,
+        count,
+        elapsed,
+        count as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,608 @@
+use crate::chart::UPCARDS;
+use crate::deck::{Card, Rank, Suit};
+use crate::game::Action;
+use crate::hand::{Hand, HandValue};
+use crate::rules::{Payout, Surrender, TableRules};
+
+use std::collections::HashMap;
+
+/// The cards left in the shoe, counted by points: the ace first, counted as 1,
+/// and every ten-value card last.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct Shoe {
+    counts: [u32; 10],
+    total: u32,
+}
+
+impl Shoe {
+    /// A full shoe of `n_decks` decks.
+    pub fn new(n_decks: u8) -> Shoe {
+        let per_rank: u32 = 4 * u32::from(n_decks);
+        let mut counts: [u32; 10] = [per_rank; 10];
+        counts[9] = 4 * per_rank;
+        Shoe {
+            counts,
+            total: 13 * per_rank,
+        }
+    }
+
+    /// A shoe holding exactly the given cards.
+    pub fn from_cards(cards: &[Card]) -> Shoe {
+        let mut shoe = Shoe {
+            counts: [0; 10],
+            total: 0,
+        };
+        for &card in cards {
+            shoe.counts[index(card)] += 1;
+            shoe.total += 1;
+        }
+        shoe
+    }
+
+    /// Number of cards left with the points of the given card.
+    pub fn count(&self, card: Card) -> u32 {
+        self.counts[index(card)]
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Takes the given card out of the shoe.
+    pub fn remove(&mut self, card: Card) {
+        let count: &mut u32 = &mut self.counts[index(card)];
+        *count = count
+            .checked_sub(1)
+            .expect("The card is not left in the shoe");
+        self.total -= 1;
+    }
+
+    /// The shoe without the given cards, e.g. the ones visible on the table.
+    pub fn without(&self, cards: &[Card]) -> Shoe {
+        let mut shoe: Shoe = *self;
+        for &card in cards {
+            shoe.remove(card);
+        }
+        shoe
+    }
+
+    fn draw(&self, points: u8) -> Shoe {
+        let mut shoe: Shoe = *self;
+        shoe.counts[usize::from(points - 1)] -= 1;
+        shoe.total -= 1;
+        shoe
+    }
+
+    /// Probability of drawing a card of the given points, between 1 and 10.
+    fn probability(&self, points: u8) -> f64 {
+        f64::from(self.counts[usize::from(points - 1)]) / f64::from(self.total)
+    }
+}
+
+fn index(card: Card) -> usize {
+    usize::from(points(card.rank()) - 1)
+}
+
+/// Points of a rank, counting the ace as 1.
+fn points(rank: Rank) -> u8 {
+    match rank {
+        Rank::Ace => 1,
+        rank => rank.value(),
+    }
+}
+
+/// Probabilities of how the dealer's hand ends.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct DealerOutcomes {
+    /// Probability of standing on each total, from 17 to 21.
+    pub totals: [f64; 5],
+    pub blackjack: f64,
+    pub bust: f64,
+}
+
+impl DealerOutcomes {
+    /// Probability of standing on the given total, between 17 and 21.
+    pub fn total(&self, total: u8) -> f64 {
+        self.totals[usize::from(total - 17)]
+    }
+}
+
+/// Works out the probabilities of the dealer's final hand given the `upcard` and the `shoe`
+/// the rest of the cards are drawn from. If the dealer peeks for blackjack,
+/// the outcomes are the ones knowing the dealer has not got one.
+pub fn dealer_outcomes(upcard: Card, shoe: &Shoe, rules: &TableRules) -> DealerOutcomes {
+    let mut outcomes = DealerOutcomes::default();
+    let upcard: u8 = points(upcard.rank());
+    dealer_draw(
+        shoe,
+        upcard,
+        upcard == 1,
+        1,
+        upcard,
+        rules.dealer_hits_soft_17,
+        rules.dealer_peeks,
+        1.0,
+        &mut outcomes,
+    );
+    outcomes
+}
+
+/// The probabilities of the dealer's final hand for every upcard, from `2` to `A`,
+/// drawing from the `shoe` without the upcard. Blackjacks are counted before peeking.
+/// Upcards with no cards left in the `shoe` are skipped.
+pub fn dealer_table(shoe: &Shoe, dealer_hits_soft_17: bool) -> Vec<(Rank, DealerOutcomes)> {
+    let rules = TableRules {
+        dealer_hits_soft_17,
+        dealer_peeks: false,
+        ..TableRules::default()
+    };
+    UPCARDS
+        .iter()
+        .map(|&rank| Card::new(rank, Suit::Spades))
+        .filter(|&upcard| shoe.count(upcard) > 0)
+        .map(|upcard| {
+            let outcomes: DealerOutcomes =
+                dealer_outcomes(upcard, &shoe.without(&[upcard]), &rules);
+            (upcard.rank(), outcomes)
+        })
+        .collect()
+}
+
+/// Adds to `outcomes` the ways the dealer's hand can end from the given state,
+/// reached with `probability`.
+#[allow(clippy::too_many_arguments)]
+fn dealer_draw(
+    shoe: &Shoe,
+    hard_total: u8,
+    has_ace: bool,
+    n_cards: u8,
+    upcard: u8,
+    hits_soft_17: bool,
+    peeked: bool,
+    probability: f64,
+    outcomes: &mut DealerOutcomes,
+) {
+    let soft: bool = has_ace && hard_total + 10 <= 21;
+    let total: u8 = if soft { hard_total + 10 } else { hard_total };
+
+    if n_cards == 2 && total == 21 {
+        outcomes.blackjack += probability;
+    } else if hard_total > 21 {
+        outcomes.bust += probability;
+    } else if total >= 17 && !(total == 17 && soft && hits_soft_17) {
+        outcomes.totals[usize::from(total - 17)] += probability;
+    } else {
+        // A peeking dealer without blackjack cannot draw the card that would make one
+        let excluded: Option<u8> = match upcard {
+            1 if n_cards == 1 && peeked => Some(10),
+            10 if n_cards == 1 && peeked => Some(1),
+            _ => None,
+        };
+        let excluded_count: u32 = excluded.map_or(0, |points| shoe.counts[usize::from(points - 1)]);
+        let remaining: f64 = f64::from(shoe.total - excluded_count);
+
+        for points in 1..=10 {
+            let count: u32 = shoe.counts[usize::from(points - 1)];
+            if count == 0 || Some(points) == excluded {
+                continue;
+            }
+            dealer_draw(
+                &shoe.draw(points),
+                hard_total + points,
+                has_ace || points == 1,
+                n_cards + 1,
+                upcard,
+                hits_soft_17,
+                peeked,
+                probability * f64::from(count) / remaining,
+                outcomes,
+            );
+        }
+    }
+}
+
+/// Expected values of every decision of a hand, as a fraction of its bet.
+/// `None` if the decision is not available.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ExpectedValues {
+    pub stand: f64,
+    pub hit: Option<f64>,
+    pub double: Option<f64>,
+    pub split: Option<f64>,
+    pub surrender: Option<f64>,
+}
+
+impl ExpectedValues {
+    /// The available decision with the highest expected value.
+    pub fn best(&self) -> (Action, f64) {
+        [
+            (Action::Hit, self.hit),
+            (Action::Double, self.double),
+            (Action::Split, self.split),
+            (Action::Surrender, self.surrender),
+        ]
+        .iter()
+        .filter_map(|&(action, value)| value.map(|value| (action, value)))
+        .fold((Action::Stand, self.stand), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+    }
+}
+
+/// Computes the expected value of standing and of every other decision in `actions`,
+/// the ones the `hand` can take right now (see `Game::available_actions`), against the
+/// dealer's `upcard`, with the rest of the cards drawn from `shoe`, which must not hold
+/// the cards already on the table.
+///
+/// Standing, hitting, doubling and surrendering are exact: after hitting, the hand keeps
+/// hitting or standing, whichever is best. Splitting is an approximation: each hand is worked
+/// out as if it were the only one drawing from the shoe, and neither can be split again.
+/// Split hands double if the rules allow it.
+pub fn expected_values(
+    hand: &Hand,
+    upcard: Card,
+    shoe: &Shoe,
+    rules: &TableRules,
+    actions: &[Action],
+) -> ExpectedValues {
+    let mut analysis = Analysis {
+        rules,
+        upcard,
+        dealer: HashMap::new(),
+        hit: HashMap::new(),
+    };
+    let state = PlayerState {
+        hard_total: hand.hard_total(),
+        has_ace: hand.cards().iter().any(|card| card.rank() == Rank::Ace),
+    };
+    let available = |action: Action| actions.contains(&action);
+
+    let stand: f64 = if hand.has_blackjack() {
+        let blackjack: f64 = analysis.dealer(shoe).blackjack;
+        let payout: Payout = rules.blackjack_payout;
+        f64::from(payout.won) / f64::from(payout.bet) * (1.0 - blackjack)
+    } else {
+        analysis.stand(state, shoe)
+    };
+
+    ExpectedValues {
+        stand,
+        hit: if available(Action::Hit) {
+            Some(analysis.hit(state, shoe))
+        } else {
+            None
+        },
+        double: if available(Action::Double) {
+            Some(analysis.double(state, shoe))
+        } else {
+            None
+        },
+        split: if available(Action::Split) {
+            Some(analysis.split(hand.cards()[0], shoe))
+        } else {
+            None
+        },
+        surrender: if available(Action::Surrender) {
+            Some(analysis.surrender(shoe))
+        } else {
+            None
+        },
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+struct PlayerState {
+    hard_total: u8,
+    has_ace: bool,
+}
+
+impl PlayerState {
+    fn total(self) -> u8 {
+        if self.has_ace && self.hard_total + 10 <= 21 {
+            self.hard_total + 10
+        } else {
+            self.hard_total
+        }
+    }
+
+    fn draw(self, points: u8) -> PlayerState {
+        PlayerState {
+            hard_total: self.hard_total + points,
+            has_ace: self.has_ace || points == 1,
+        }
+    }
+}
+
+/// The expected values already worked out for a hand, as the same shoe is reached
+/// through many orders of cards.
+struct Analysis<'a> {
+    rules: &'a TableRules,
+    upcard: Card,
+    dealer: HashMap<Shoe, DealerOutcomes>,
+    hit: HashMap<(PlayerState, Shoe), f64>,
+}
+
+impl<'a> Analysis<'a> {
+    fn dealer(&mut self, shoe: &Shoe) -> DealerOutcomes {
+        let (upcard, rules) = (self.upcard, self.rules);
+        *self
+            .dealer
+            .entry(*shoe)
+            .or_insert_with(|| dealer_outcomes(upcard, shoe, rules))
+    }
+
+    fn stand(&mut self, state: PlayerState, shoe: &Shoe) -> f64 {
+        let dealer: DealerOutcomes = self.dealer(shoe);
+        let total: u8 = state.total();
+        if total > 21 {
+            return -1.0;
+        }
+        let mut value: f64 = dealer.bust - dealer.blackjack;
+        for dealer_total in 17..=21 {
+            if dealer_total < total {
+                value += dealer.total(dealer_total);
+            } else if dealer_total > total {
+                value -= dealer.total(dealer_total);
+            }
+        }
+        value
+    }
+
+    /// Hits once and then hits or stands, whichever is best.
+    fn hit(&mut self, state: PlayerState, shoe: &Shoe) -> f64 {
+        if let Some(&value) = self.hit.get(&(state, *shoe)) {
+            return value;
+        }
+        let mut value: f64 = 0.0;
+        for points in 1..=10 {
+            if shoe.counts[usize::from(points - 1)] == 0 {
+                continue;
+            }
+            let next: PlayerState = state.draw(points);
+            let next_shoe: Shoe = shoe.draw(points);
+            let next_value: f64 = if next.hard_total > 21 {
+                -1.0
+            } else if next.total() == 21 {
+                self.stand(next, &next_shoe)
+            } else {
+                self.stand(next, &next_shoe).max(self.hit(next, &next_shoe))
+            };
+            value += shoe.probability(points) * next_value;
+        }
+        self.hit.insert((state, *shoe), value);
+        value
+    }
+
+    /// Doubles the bet and takes a single card.
+    fn double(&mut self, state: PlayerState, shoe: &Shoe) -> f64 {
+        let mut value: f64 = 0.0;
+        for points in 1..=10 {
+            if shoe.counts[usize::from(points - 1)] == 0 {
+                continue;
+            }
+            value += shoe.probability(points) * self.stand(state.draw(points), &shoe.draw(points));
+        }
+        2.0 * value
+    }
+
+    /// Splits the pair of `card`s into two hands with the same expected value,
+    /// each of them getting a card and being played as well as possible without splitting again.
+    fn split(&mut self, card: Card, shoe: &Shoe) -> f64 {
+        let rules: &TableRules = self.rules;
+        let start = PlayerState {
+            hard_total: 0,
+            has_ace: false,
+        }
+        .draw(points(card.rank()));
+        let split_aces: bool = card.rank() == Rank::Ace && !rules.hit_split_aces;
+
+        let mut value: f64 = 0.0;
+        for points in 1..=10 {
+            if shoe.counts[usize::from(points - 1)] == 0 {
+                continue;
+            }
+            let state: PlayerState = start.draw(points);
+            let next_shoe: Shoe = shoe.draw(points);
+            let mut hand_value: f64 = self.stand(state, &next_shoe);
+            if !split_aces && state.total() < 21 {
+                hand_value = hand_value.max(self.hit(state, &next_shoe));
+                let total: HandValue = if state.total() == state.hard_total {
+                    HandValue::Hard(state.total())
+                } else {
+                    HandValue::Soft(state.total())
+                };
+                if rules.double_after_split && rules.double_on.allows(total) {
+                    hand_value = hand_value.max(self.double(state, &next_shoe));
+                }
+            }
+            value += shoe.probability(points) * hand_value;
+        }
+        2.0 * value
+    }
+
+    fn surrender(&mut self, shoe: &Shoe) -> f64 {
+        // Only late surrender against a dealer who did not peek can lose to a blackjack
+        if self.rules.surrender == Surrender::Late && !self.rules.dealer_peeks {
+            let blackjack: f64 = self.dealer(shoe).blackjack;
+            -0.5 * (1.0 - blackjack) - blackjack
+        } else {
+            -0.5
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Deck;
+
+    const TOLERANCE: f64 = 0.0005;
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Hearts)
+    }
+
+    const ALL_ACTIONS: [Action; 5] = [
+        Action::Hit,
+        Action::Stand,
+        Action::Split,
+        Action::Double,
+        Action::Surrender,
+    ];
+
+    /// Expected values of the hand of `ranks` against the `upcard`, from a 6 deck S17 shoe,
+    /// given the available `actions`.
+    fn values_of(ranks: &[Rank], upcard: Rank, actions: &[Action]) -> ExpectedValues {
+        let cards: Vec<Card> = ranks.iter().map(|&rank| card(rank)).collect();
+        let shoe: Shoe = Shoe::new(6).without(&cards).without(&[card(upcard)]);
+        expected_values(
+            &Hand::from_cards(&cards),
+            card(upcard),
+            &shoe,
+            &TableRules::default(),
+            actions,
+        )
+    }
+
+    /// Expected values of the hand of `ranks` against the `upcard`, as dealt.
+    fn values(ranks: &[Rank], upcard: Rank) -> ExpectedValues {
+        let pair: bool = ranks[0] == ranks[1];
+        let actions: Vec<Action> = ALL_ACTIONS
+            .iter()
+            .cloned()
+            .filter(|&action| pair || action != Action::Split)
+            .collect();
+        values_of(ranks, upcard, &actions)
+    }
+
+    fn outcomes(upcard: Rank) -> DealerOutcomes {
+        let shoe: Shoe = Shoe::new(6).without(&[card(upcard)]);
+        dealer_outcomes(card(upcard), &shoe, &TableRules::default())
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < TOLERANCE,
+            "{} is not close to {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn shoe_from_cards_counts_every_card() {
+        let mut deck = Deck::from_seed(2, 0);
+        let cards: Vec<Card> = (0..deck.remaining()).map(|_| deck.deal_card()).collect();
+
+        assert_eq!(Shoe::from_cards(&cards), Shoe::new(2));
+        assert_eq!(
+            Shoe::from_cards(&cards[1..]),
+            Shoe::new(2).without(&cards[..1])
+        );
+    }
+
+    #[test]
+    fn dealer_outcomes_match_published_tables() {
+        let six: DealerOutcomes = outcomes(Rank::Six);
+        assert_close(six.total(17), 0.1657);
+        assert_close(six.total(21), 0.0973);
+        assert_close(six.bust, 0.4228);
+
+        // Knowing the dealer peeked and has no blackjack
+        let ten: DealerOutcomes = outcomes(Rank::Ten);
+        assert_close(ten.total(20), 0.3684);
+        assert_close(ten.bust, 0.2302);
+        assert_eq!(ten.blackjack, 0.0);
+    }
+
+    #[test]
+    fn dealer_outcomes_add_up_to_one() {
+        for &upcard in UPCARDS.iter() {
+            let outcomes: DealerOutcomes = outcomes(upcard);
+            let sum: f64 = outcomes.totals.iter().sum::<f64>() + outcomes.blackjack + outcomes.bust;
+            assert_close(sum, 1.0);
+        }
+    }
+
+    #[test]
+    fn dealer_table_counts_blackjacks_before_peeking() {
+        let table: Vec<(Rank, DealerOutcomes)> = dealer_table(&Shoe::new(6), false);
+        let (upcard, ace): (Rank, DealerOutcomes) = table[9];
+
+        assert_eq!(table.len(), 10);
+        assert_eq!(upcard, Rank::Ace);
+        assert_close(ace.blackjack, 0.3087);
+        assert_close(table[4].1.bust, 0.4228);
+    }
+
+    #[test]
+    fn dealer_table_skips_upcards_not_in_the_shoe() {
+        let aces: Vec<Card> = Suit::iter()
+            .map(|suit| Card::new(Rank::Ace, suit))
+            .collect();
+        let table: Vec<(Rank, DealerOutcomes)> = dealer_table(&Shoe::new(1).without(&aces), true);
+
+        assert_eq!(table.len(), 9);
+        assert!(table
+            .iter()
+            .all(|&(upcard, outcomes)| upcard != Rank::Ace && outcomes.blackjack == 0.0));
+        assert!(dealer_table(&Shoe::new(0), false).is_empty());
+    }
+
+    #[test]
+    fn sixteen_against_ten() {
+        let values: ExpectedValues = values(&[Rank::Ten, Rank::Six], Rank::Ten);
+
+        assert_close(values.stand, -0.5410);
+        assert_close(values.hit.unwrap(), -0.5347);
+        assert_eq!(values.split, None);
+        assert_eq!(values.surrender, Some(-0.5));
+        assert_eq!(values.best().0, Action::Surrender);
+    }
+
+    #[test]
+    fn eleven_against_six_doubles() {
+        let values: ExpectedValues = values(&[Rank::Six, Rank::Five], Rank::Six);
+
+        assert_close(values.double.unwrap(), 0.6827);
+        assert_close(values.double.unwrap(), 2.0 * values.hit.unwrap());
+        assert_eq!(values.best().0, Action::Double);
+    }
+
+    #[test]
+    fn eights_against_ten_split() {
+        let values: ExpectedValues = values(&[Rank::Eight, Rank::Eight], Rank::Ten);
+
+        assert_eq!(values.best().0, Action::Split);
+    }
+
+    #[test]
+    fn only_available_actions_have_a_value() {
+        // Split aces that can take no more cards can only stand
+        let values: ExpectedValues =
+            values_of(&[Rank::Ace, Rank::Six], Rank::Ten, &[Action::Stand]);
+
+        assert_eq!(values.hit, None);
+        assert_eq!(values.double, None);
+        assert_eq!(values.split, None);
+        assert_eq!(values.surrender, None);
+        assert_eq!(values.best(), (Action::Stand, values.stand));
+
+        // Aces that cannot be split again
+        let actions: [Action; 3] = [Action::Hit, Action::Stand, Action::Double];
+        let values: ExpectedValues = values_of(&[Rank::Ace, Rank::Ace], Rank::Six, &actions);
+        assert_eq!(values.split, None);
+        assert_ne!(values.best().0, Action::Split);
+    }
+
+    #[test]
+    fn blackjack_against_a_six_wins_the_payout() {
+        let values: ExpectedValues = values(&[Rank::Ace, Rank::King], Rank::Six);
+
+        assert_close(values.stand, 1.5);
+    }
+}
@@ -0,0 +1,30 @@
+//! Command line options shared by the game and the simulator.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Reads the value of an option like `--seed <number>`, `None` if it is not given.
+/// Returns an error message if the value is missing or cannot be parsed.
+pub fn option<T>(args: &[String], option: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let position: usize = match args.iter().position(|arg| arg == option) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+
+    match args.get(position + 1) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|error| format!("Invalid value for {}: {}", option, error)),
+        None => Err(format!("Missing value for {}", option)),
+    }
+}
+
+/// Whether a flag like `--h17` is present.
+pub fn flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
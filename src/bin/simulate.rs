@@ -0,0 +1,74 @@
+use std::env;
+use std::process;
+use std::thread;
+
+use blackjack_rust::args;
+use blackjack_rust::chart::Chart;
+use blackjack_rust::deck::DEFAULT_PENETRATION;
+use blackjack_rust::rules::TableRules;
+use blackjack_rust::simulation::{self, SimulationStats};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(error) = run(&args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let rules: TableRules = TableRules::from_args(args)?;
+    if simulation::BET < rules.min_bet || simulation::BET > rules.max_bet {
+        return Err(format!(
+            "The simulated bet of {} is outside the table limits",
+            simulation::BET
+        ));
+    }
+    let rounds: u64 = args::option(args, "--rounds")?.unwrap_or(1_000_000);
+    if rounds == 0 {
+        return Err(String::from(
+            "Invalid value for --rounds: at least one round must be played",
+        ));
+    }
+    let seed: u64 = args::option(args, "--seed")?.unwrap_or_else(rand::random);
+    let penetration: f64 = args::option(args, "--penetration")?.unwrap_or(DEFAULT_PENETRATION);
+    let chart: Chart = match args::option::<String>(args, "--chart")? {
+        Some(path) => Chart::load(&path, &rules)
+            .map_err(|error| format!("Invalid strategy chart {}: {}", path, error))?,
+        None => Chart::basic(&rules),
+    };
+    let threads: usize = args::option(args, "--threads")?
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+
+    println!("Rules: {}", rules);
+    println!(
+        "Simulating {} rounds with seed {} on {} threads...",
+        rounds, seed, threads
+    );
+    let stats: SimulationStats =
+        simulation::simulate_parallel(&rules, &chart, rounds, seed, penetration, threads);
+    print_stats(&stats);
+    Ok(())
+}
+
+fn print_stats(stats: &SimulationStats) {
+    println!("\nRounds: {} ({} hands)", stats.rounds, stats.hands);
+    let (low, high) = stats.confidence_interval(1.96);
+    println!(
+        "House edge: {:.3}% (95% confidence interval: {:.3}% to {:.3}%)",
+        stats.house_edge() * 100.0,
+        low * 100.0,
+        high * 100.0
+    );
+    println!("Standard deviation: {:.3} bets per round", stats.std_dev());
+    println!(
+        "Wins: {:.2}%, losses: {:.2}%, pushes: {:.2}% of the hands",
+        stats.win_rate() * 100.0,
+        stats.loss_rate() * 100.0,
+        stats.push_rate() * 100.0
+    );
+    println!(
+        "Blackjacks: {:.2}% of the rounds",
+        stats.blackjack_rate() * 100.0
+    );
+}
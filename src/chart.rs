@@ -0,0 +1,505 @@
+use crate::deck::{Card, Rank};
+use crate::game::Action;
+use crate::hand::Hand;
+use crate::rules::TableRules;
+use crate::strategy::{self, Play};
+
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// The dealer upcards heading the columns of a chart, in order.
+pub const UPCARDS: [Rank; 10] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Ace,
+];
+
+/// Error returned when a `Chart` cannot be loaded.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ChartError {
+    /// The file could not be read.
+    Io(String),
+    /// A cell of the chart is not valid. `row` and `column` start at 1.
+    Invalid {
+        row: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChartError::Io(message) => write!(f, "cannot read the chart: {}", message),
+            ChartError::Invalid {
+                row,
+                column,
+                message,
+            } => write!(f, "row {}, column {}: {}", row, column, message),
+        }
+    }
+}
+
+impl Error for ChartError {}
+
+impl ChartError {
+    fn invalid(row: usize, column: usize, message: String) -> ChartError {
+        ChartError::Invalid {
+            row,
+            column,
+            message,
+        }
+    }
+}
+
+/// A row of a chart, labelled `H<total>`, `S<total>` or `P<rank>` in the files.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum ChartRow {
+    Hard(u8),
+    Soft(u8),
+    /// A pair, by the value of its cards.
+    Pair(u8),
+}
+
+impl ChartRow {
+    fn parse(label: &str) -> Result<ChartRow, String> {
+        let upper: String = label.trim().to_uppercase();
+        let mut chars = upper.chars();
+        let kind: Option<char> = chars.next();
+        let rest: &str = chars.as_str();
+
+        let row: Option<ChartRow> = match kind {
+            Some('H') => rest
+                .parse()
+                .ok()
+                .filter(|total| (4..=21).contains(total))
+                .map(ChartRow::Hard),
+            Some('S') => rest
+                .parse()
+                .ok()
+                .filter(|total| (12..=21).contains(total))
+                .map(ChartRow::Soft),
+            Some('P') => rest
+                .parse::<Rank>()
+                .ok()
+                .map(|rank| ChartRow::Pair(rank.value())),
+            _ => None,
+        };
+        row.ok_or_else(|| format!("'{}' is not a hand (H4-H21, S12-S21, P2-PA)", label))
+    }
+}
+
+/// A strategy chart: the play for every hard total, soft total and pair
+/// against every dealer upcard.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Chart {
+    hard: [[Play; 10]; 22],
+    soft: [[Play; 10]; 22],
+    pairs: [[Play; 10]; 12],
+}
+
+impl Chart {
+    /// The basic strategy chart for the `rules`.
+    pub fn basic(rules: &TableRules) -> Chart {
+        let mut chart = Chart {
+            hard: [[Play::Hit; 10]; 22],
+            soft: [[Play::Hit; 10]; 22],
+            pairs: [[Play::Hit; 10]; 12],
+        };
+        for (column, &upcard) in UPCARDS.iter().enumerate() {
+            for total in 4..=21 {
+                chart.hard[usize::from(total)][column] = strategy::hard_play(total, upcard, rules);
+            }
+            for total in 12..=21 {
+                chart.soft[usize::from(total)][column] = strategy::soft_play(total, upcard, rules);
+            }
+            for &rank in UPCARDS.iter() {
+                let play: Play = match strategy::pair_play(rank, upcard, rules) {
+                    Some(play) => play,
+                    None if rank == Rank::Ace => strategy::soft_play(12, upcard, rules),
+                    None => strategy::hard_play(rank.value() * 2, upcard, rules),
+                };
+                chart.pairs[usize::from(rank.value())][column] = play;
+            }
+        }
+        chart
+    }
+
+    /// Loads a chart from a `.toml` file, or a CSV file otherwise.
+    /// Hands missing from the file are played with the basic strategy for the `rules`.
+    pub fn load<P: AsRef<Path>>(path: P, rules: &TableRules) -> Result<Chart, ChartError> {
+        let path: &Path = path.as_ref();
+        let text: String =
+            fs::read_to_string(path).map_err(|error| ChartError::Io(error.to_string()))?;
+        if path.extension() == Some(OsStr::new("toml")) {
+            Chart::from_toml(&text, rules)
+        } else {
+            Chart::from_csv(&text, rules)
+        }
+    }
+
+    /// Parses a chart whose first row holds the upcards (`2` to `10` and `A`, in any order)
+    /// and every other row a hand label (`H16`, `S18`, `P8`, `PA`...) followed by a play per upcard.
+    /// Empty lines and lines starting with `#` are ignored.
+    /// Hands missing from the text are played with the basic strategy for the `rules`.
+    pub fn from_csv(text: &str, rules: &TableRules) -> Result<Chart, ChartError> {
+        let mut chart: Chart = Chart::basic(rules);
+        let mut columns: Option<Vec<usize>> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let row: usize = index + 1;
+            let line: &str = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+
+            let columns: &[usize] = match &columns {
+                Some(columns) => columns,
+                None => {
+                    columns = Some(parse_header(&cells, row)?);
+                    continue;
+                }
+            };
+            let chart_row: ChartRow = ChartRow::parse(cells[0])
+                .map_err(|message| ChartError::invalid(row, 1, message))?;
+            if cells.len() != columns.len() + 1 {
+                return Err(ChartError::invalid(
+                    row,
+                    cells.len().min(columns.len() + 1) + 1,
+                    format!(
+                        "expected {} plays, one per upcard, found {}",
+                        columns.len(),
+                        cells.len() - 1
+                    ),
+                ));
+            }
+            for (cell_index, (cell, &column)) in cells[1..].iter().zip(columns).enumerate() {
+                chart
+                    .set(chart_row, column, cell)
+                    .map_err(|message| ChartError::invalid(row, cell_index + 2, message))?;
+            }
+        }
+        Ok(chart)
+    }
+
+    /// Parses a chart made of `[hard]`, `[soft]` and `[pairs]` tables, whose keys are the
+    /// totals or ranks and whose values are arrays of plays for the upcards `2` to `10` and `A`:
+    ///
+    /// ```toml
+    /// [hard]
+    /// 16 = ["S", "S", "S", "S", "S", "H", "H", "Rh", "Rh", "Rh"]
+    /// ```
+    ///
+    /// Only this subset of TOML is supported: every array must fit on its line,
+    /// and `#` starts a comment anywhere. The column of an error is the position
+    /// of the play in the array.
+    /// Hands missing from the text are played with the basic strategy for the `rules`.
+    pub fn from_toml(text: &str, rules: &TableRules) -> Result<Chart, ChartError> {
+        let mut chart: Chart = Chart::basic(rules);
+        let mut section: Option<char> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let row: usize = index + 1;
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = match line {
+                    "[hard]" => Some('H'),
+                    "[soft]" => Some('S'),
+                    "[pairs]" => Some('P'),
+                    _ => {
+                        return Err(ChartError::invalid(
+                            row,
+                            1,
+                            format!("'{}' is not a table ([hard], [soft], [pairs])", line),
+                        ))
+                    }
+                };
+                continue;
+            }
+
+            let (key, value): (&str, &str) = match line.find('=') {
+                Some(position) => (&line[..position], line[position + 1..].trim()),
+                None => {
+                    return Err(ChartError::invalid(
+                        row,
+                        1,
+                        "expected a hand and its plays, like `16 = [\"S\", ...]`".to_string(),
+                    ))
+                }
+            };
+            let prefix: char = section.ok_or_else(|| {
+                ChartError::invalid(
+                    row,
+                    1,
+                    "hands must be inside a [hard], [soft] or [pairs] table".to_string(),
+                )
+            })?;
+            let label: String = format!("{}{}", prefix, key.trim().trim_matches('"'));
+            let chart_row: ChartRow =
+                ChartRow::parse(&label).map_err(|message| ChartError::invalid(row, 1, message))?;
+            if !value.starts_with('[') {
+                return Err(ChartError::invalid(
+                    row,
+                    1,
+                    "expected an array of plays".to_string(),
+                ));
+            } else if !value.ends_with(']') {
+                return Err(ChartError::invalid(
+                    row,
+                    1,
+                    "the array of plays must end on the same line".to_string(),
+                ));
+            }
+            let mut cells: Vec<&str> = value[1..value.len() - 1]
+                .split(',')
+                .map(|cell| cell.trim().trim_matches('"'))
+                .collect();
+            // TOML allows a comma after the last element
+            if cells.len() > 1 && cells.last() == Some(&"") {
+                cells.pop();
+            }
+            if let Some(empty) = cells.iter().position(|cell| cell.is_empty()) {
+                return Err(ChartError::invalid(
+                    row,
+                    empty + 1,
+                    "expected a play".to_string(),
+                ));
+            }
+            if cells.len() != UPCARDS.len() {
+                return Err(ChartError::invalid(
+                    row,
+                    cells.len().min(UPCARDS.len()) + 1,
+                    format!(
+                        "expected {} plays, one per upcard, found {}",
+                        UPCARDS.len(),
+                        cells.len()
+                    ),
+                ));
+            }
+            for (column, cell) in cells.iter().enumerate() {
+                chart
+                    .set(chart_row, column, cell)
+                    .map_err(|message| ChartError::invalid(row, column + 1, message))?;
+            }
+        }
+        Ok(chart)
+    }
+
+    /// The play of the chart for the `hand` against the dealer's `upcard`,
+    /// reading the pairs rows only if the hand can be split.
+    pub fn play(&self, hand: &Hand, upcard: Card, actions: &[Action]) -> Play {
+        let column: usize = upcard_column(upcard.rank());
+        let total: usize = usize::from(hand.points().min(21));
+
+        if hand.is_pair() && actions.contains(&Action::Split) {
+            self.pairs[usize::from(hand.cards()[0].value())][column]
+        } else if hand.is_soft() {
+            self.soft[total][column]
+        } else {
+            self.hard[total][column]
+        }
+    }
+
+    /// The action to take with the `hand` against the dealer's `upcard`,
+    /// given the `actions` it can take right now.
+    pub fn recommend(&self, hand: &Hand, upcard: Card, actions: &[Action]) -> Action {
+        self.play(hand, upcard, actions).resolve(actions)
+    }
+
+    /// Sets the play of the `cell` in the given row and column, checking that it can be
+    /// played there.
+    fn set(&mut self, row: ChartRow, column: usize, cell: &str) -> Result<(), String> {
+        let play: Play = cell.parse()?;
+        let splits: bool = play == Play::Split || play == Play::SurrenderOrSplit;
+        match row {
+            ChartRow::Pair(value) => self.pairs[usize::from(value)][column] = play,
+            _ if splits => return Err(format!("'{}' splits a hand that is not a pair", cell)),
+            ChartRow::Hard(total) => self.hard[usize::from(total)][column] = play,
+            ChartRow::Soft(total) => self.soft[usize::from(total)][column] = play,
+        }
+        Ok(())
+    }
+}
+
+fn upcard_column(upcard: Rank) -> usize {
+    UPCARDS
+        .iter()
+        .position(|rank| rank.value() == upcard.value())
+        .expect("Every rank has the value of an upcard")
+}
+
+/// Maps every upcard of the header row to its column in the chart.
+fn parse_header(cells: &[&str], row: usize) -> Result<Vec<usize>, ChartError> {
+    let mut columns: Vec<usize> = Vec::new();
+    for (index, cell) in cells.iter().enumerate().skip(1) {
+        let column: usize = cell
+            .parse::<Rank>()
+            .map(upcard_column)
+            .map_err(|error| ChartError::invalid(row, index + 1, error.to_string()))?;
+        if columns.contains(&column) {
+            return Err(ChartError::invalid(
+                row,
+                index + 1,
+                format!("the upcard '{}' is repeated", cell),
+            ));
+        }
+        columns.push(column);
+    }
+    if columns.len() != UPCARDS.len() {
+        return Err(ChartError::invalid(
+            row,
+            cells.len() + 1,
+            format!(
+                "expected the {} upcards 2-10 and A, found {}",
+                UPCARDS.len(),
+                columns.len()
+            ),
+        ));
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "hand,2,3,4,5,6,7,8,9,10,A";
+
+    fn csv_error(text: &str) -> (usize, usize) {
+        match Chart::from_csv(text, &TableRules::default()) {
+            Err(ChartError::Invalid { row, column, .. }) => (row, column),
+            other => panic!("expected an invalid chart, got {:?}", other),
+        }
+    }
+
+    fn toml_error(text: &str) -> (usize, usize) {
+        match Chart::from_toml(text, &TableRules::default()) {
+            Err(ChartError::Invalid { row, column, .. }) => (row, column),
+            other => panic!("expected an invalid chart, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn csv_rows_override_the_basic_strategy() {
+        let text = format!(
+            "{}\nH16,H,H,H,H,H,H,H,H,H,S\nPA,S,S,S,S,S,S,S,S,S,S",
+            HEADER
+        );
+        let chart: Chart = Chart::from_csv(&text, &TableRules::default()).unwrap();
+        let basic: Chart = Chart::basic(&TableRules::default());
+
+        assert_eq!(chart.hard[16][0], Play::Hit);
+        assert_eq!(chart.hard[16][9], Play::Stand);
+        assert_eq!(chart.pairs[11], [Play::Stand; 10]);
+        assert_eq!(chart.hard[15], basic.hard[15]);
+        assert_eq!(chart.soft, basic.soft);
+    }
+
+    #[test]
+    fn csv_columns_follow_the_header() {
+        let text = "hand,A,10,9,8,7,6,5,4,3,2\nH12,H,S,S,S,S,S,S,S,S,S";
+        let chart: Chart = Chart::from_csv(text, &TableRules::default()).unwrap();
+
+        assert_eq!(chart.hard[12][9], Play::Hit);
+        assert_eq!(chart.hard[12][..9], [Play::Stand; 9]);
+    }
+
+    #[test]
+    fn csv_header_errors_point_at_the_upcard() {
+        assert_eq!(csv_error("hand,2,3,X,5,6,7,8,9,10,A"), (1, 4));
+        assert_eq!(csv_error("hand,2,3,4,5,6,7,8,9,10,2"), (1, 11));
+        assert_eq!(csv_error("hand,2,3,4,5,6,7,8,9,10"), (1, 11));
+    }
+
+    #[test]
+    fn csv_errors_point_at_the_cell() {
+        assert_eq!(
+            csv_error(&format!("{}\nX16,S,S,S,S,S,H,H,H,H,H", HEADER)),
+            (2, 1)
+        );
+        assert_eq!(
+            csv_error(&format!("{}\nH16,S,S,S,S,S,H,H,Q,H,H", HEADER)),
+            (2, 9)
+        );
+        assert_eq!(
+            csv_error(&format!("{}\nH16,S,S,S,S,S,H,H,,H,H", HEADER)),
+            (2, 9)
+        );
+        assert_eq!(
+            csv_error(&format!("{}\nH16,S,P,S,S,S,H,H,H,H,H", HEADER)),
+            (2, 3)
+        );
+        assert_eq!(
+            csv_error(&format!("{}\nH16,S,S,S,S,S,H,H,H", HEADER)),
+            (2, 10)
+        );
+        assert_eq!(
+            csv_error(&format!("{}\nH16,S,S,S,S,S,H,H,H,H,H,H", HEADER)),
+            (2, 12)
+        );
+    }
+
+    #[test]
+    fn csv_rows_count_comments_and_empty_lines() {
+        let text = format!("# My chart\n\n{}\n\nH16,S,S,S,S,S,H,H,Q,H,H", HEADER);
+
+        assert_eq!(csv_error(&text), (5, 9));
+    }
+
+    #[test]
+    fn toml_tables_override_the_basic_strategy() {
+        let text = "# My chart\n\
+                    [hard]\n\
+                    16 = [\"H\", \"H\", \"H\", \"H\", \"H\", \"H\", \"H\", \"H\", \"H\", \"S\"]\n\
+                    [soft]\n\
+                    \"18\" = [\"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"H\", \"H\", \"H\",]\n\
+                    [pairs]\n\
+                    A = [\"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"S\", \"S\"] # never split";
+        let chart: Chart = Chart::from_toml(text, &TableRules::default()).unwrap();
+
+        assert_eq!(chart.hard[16][0], Play::Hit);
+        assert_eq!(chart.hard[16][9], Play::Stand);
+        assert_eq!(chart.soft[18][0], Play::Stand);
+        assert_eq!(chart.soft[18][9], Play::Hit);
+        assert_eq!(chart.pairs[11], [Play::Stand; 10]);
+    }
+
+    #[test]
+    fn toml_errors_point_at_the_play() {
+        let plays = "\"S\", \"S\", \"Q\", \"S\", \"S\", \"H\", \"H\", \"H\", \"H\", \"H\"";
+        assert_eq!(toml_error(&format!("[hard]\n16 = [{}]", plays)), (2, 3));
+
+        let plays = "\"S\", , \"S\", \"S\", \"S\", \"H\", \"H\", \"H\", \"H\", \"H\"";
+        assert_eq!(toml_error(&format!("[hard]\n16 = [{}]", plays)), (2, 2));
+
+        let plays = "\"S\", \"S\", \"S\"";
+        assert_eq!(toml_error(&format!("\n[hard]\n16 = [{}]", plays)), (3, 4));
+
+        let plays = "\"S\", \"P\", \"S\", \"S\", \"S\", \"H\", \"H\", \"H\", \"H\", \"H\"";
+        assert_eq!(toml_error(&format!("[soft]\n16 = [{}]", plays)), (2, 2));
+    }
+
+    #[test]
+    fn toml_line_errors_point_at_the_first_column() {
+        assert_eq!(toml_error("[doubles]"), (1, 1));
+        assert_eq!(toml_error("16 = [\"S\"]"), (1, 1));
+        assert_eq!(toml_error("[hard]\n3 = []"), (2, 1));
+        assert_eq!(toml_error("[hard]\n16 = \"S\""), (2, 1));
+        assert_eq!(toml_error("[hard]\n16 = [\n\"S\",\n]"), (2, 1));
+        assert_eq!(toml_error("[hard]\n16"), (2, 1));
+    }
+}
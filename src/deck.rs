@@ -1,73 +1,504 @@
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
-use rand;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
+/// Error returned when a `Rank`, `Suit` or `Card` cannot be parsed from a string.
 #[derive(PartialEq, Debug, Clone)]
-pub struct Card {
-    pub name: String,
-    suit: char,
+pub struct ParseCardError {
+    input: String,
 }
 
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' is not a valid card", self.input)
+    }
+}
+
+impl Error for ParseCardError {}
+
+impl ParseCardError {
+    fn new(input: &str) -> ParseCardError {
+        ParseCardError {
+            input: input.to_string(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum Rank {
+    Ace,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rank::Ace => "ACE",
+            Rank::Two => "TWO",
+            Rank::Three => "THREE",
+            Rank::Four => "FOUR",
+            Rank::Five => "FIVE",
+            Rank::Six => "SIX",
+            Rank::Seven => "SEVEN",
+            Rank::Eight => "EIGHT",
+            Rank::Nine => "NINE",
+            Rank::Ten => "TEN",
+            Rank::Jack => "JACK",
+            Rank::Queen => "QUEEN",
+            Rank::King => "KING",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseCardError;
+
+    /// Parses either the full name (`"ACE"`) or the short form (`"A"`, `"10"`, `"T"`),
+    /// ignoring case and surrounding whitespace.
+    fn from_str(s: &str) -> Result<Rank, ParseCardError> {
+        match s.trim().to_uppercase().as_str() {
+            "ACE" | "A" | "1" => Ok(Rank::Ace),
+            "TWO" | "2" => Ok(Rank::Two),
+            "THREE" | "3" => Ok(Rank::Three),
+            "FOUR" | "4" => Ok(Rank::Four),
+            "FIVE" | "5" => Ok(Rank::Five),
+            "SIX" | "6" => Ok(Rank::Six),
+            "SEVEN" | "7" => Ok(Rank::Seven),
+            "EIGHT" | "8" => Ok(Rank::Eight),
+            "NINE" | "9" => Ok(Rank::Nine),
+            "TEN" | "10" | "T" => Ok(Rank::Ten),
+            "JACK" | "J" => Ok(Rank::Jack),
+            "QUEEN" | "Q" => Ok(Rank::Queen),
+            "KING" | "K" => Ok(Rank::King),
+            _ => Err(ParseCardError::new(s)),
+        }
+    }
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Ace,
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+    ];
+
+    /// Iterates over every rank, from `Ace` to `King`.
+    pub fn iter() -> impl Iterator<Item = Rank> {
+        Rank::ALL.iter().cloned()
+    }
+
+    /// Returns the points of the rank, counting the ace as 11.
+    pub fn value(self) -> u8 {
+        match self {
+            Rank::Ace => 11,
+            Rank::Two => 2,
+            Rank::Three => 3,
+            Rank::Four => 4,
+            Rank::Five => 5,
+            Rank::Six => 6,
+            Rank::Seven => 7,
+            Rank::Eight => 8,
+            Rank::Nine => 9,
+            Rank::Ten | Rank::Jack | Rank::Queen | Rank::King => 10,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum Suit {
+    Hearts,
+    Diamonds,
+    Clubs,
+    Spades,
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    /// Parses either the symbol (`"♥"`), the name (`"hearts"`) or its initial (`"H"`),
+    /// ignoring case and surrounding whitespace.
+    fn from_str(s: &str) -> Result<Suit, ParseCardError> {
+        match s.trim().to_uppercase().as_str() {
+            "♥" | "H" | "HEARTS" => Ok(Suit::Hearts),
+            "♦" | "D" | "DIAMONDS" => Ok(Suit::Diamonds),
+            "♣" | "C" | "CLUBS" => Ok(Suit::Clubs),
+            "♠" | "S" | "SPADES" => Ok(Suit::Spades),
+            _ => Err(ParseCardError::new(s)),
+        }
+    }
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
+
+    /// Iterates over every suit.
+    pub fn iter() -> impl Iterator<Item = Suit> {
+        Suit::ALL.iter().cloned()
+    }
+
+    pub fn symbol(self) -> char {
+        match self {
+            Suit::Hearts => '♥',
+            Suit::Diamonds => '♦',
+            Suit::Clubs => '♣',
+            Suit::Spades => '♠',
+        }
+    }
+}
+
+/// A card packed in a single byte, the rank in the high bits and the suit in the two low bits,
+/// so cards are ordered by rank first.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Card(u8);
+
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} of {}", self.name, self.suit)
+        write!(f, "{} of {}", self.rank(), self.suit())
     }
 }
 
-impl Card {
-    pub fn name_to_value(&self) -> u8 {
-        match self.name.to_uppercase().trim() {
-            "ACE" => 11,
-            "TWO" => 2,
-            "THREE" => 3,
-            "FOUR" => 4,
-            "FIVE" => 5,
-            "SIX" => 6,
-            "SEVEN" => 7,
-            "EIGHT" => 8,
-            "NINE" => 9,
-            "TEN" | "JACK" | "QUEEN" | "KING" => 10,
-            _ => panic!("Name not valid."),
+impl fmt::Debug for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Card({})", self)
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    /// Parses the format produced by `Display` (`"ACE of ♥"`)
+    /// or a short form made of the rank followed by the suit (`"AH"`, `"10♠"`).
+    fn from_str(s: &str) -> Result<Card, ParseCardError> {
+        let trimmed = s.trim();
+        let (rank, suit) = match trimmed.find(" of ") {
+            Some(index) => (&trimmed[..index], &trimmed[index + 4..]),
+            None => match trimmed.char_indices().last() {
+                Some((index, _)) => trimmed.split_at(index),
+                None => return Err(ParseCardError::new(s)),
+            },
+        };
+        match (rank.parse(), suit.parse()) {
+            (Ok(rank), Ok(suit)) => Ok(Card::new(rank, suit)),
+            _ => Err(ParseCardError::new(s)),
         }
     }
 }
 
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Card {
+        Card((rank as u8) << 2 | suit as u8)
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::ALL[usize::from(self.0 >> 2)]
+    }
+
+    pub fn suit(self) -> Suit {
+        Suit::ALL[usize::from(self.0 & 0b11)]
+    }
+
+    pub fn value(self) -> u8 {
+        self.rank().value()
+    }
+}
+
+/// Fraction of the shoe dealt before the cut card comes out, if not configured otherwise.
+pub const DEFAULT_PENETRATION: f64 = 0.75;
+
+/// A shoe made of one or more decks, with a cut card and a discard tray.
 pub struct Deck {
     cards: Vec<Card>,
+    discards: Vec<Card>,
+    /// Number of cards left in the shoe when the cut card is reached.
+    cut_card: usize,
+    n_cards: usize,
+    /// Cards shuffled back in because the shoe ran out in the middle of a round,
+    /// not reported yet.
+    emergency_shuffle: Option<usize>,
+    rng: StdRng,
+    seed: u64,
 }
 
 impl Deck {
+    /// Creates a shuffled `Deck` from a random seed.
     pub fn new(n_decks: u8) -> Deck {
-        let suits: [char; 4] = ['♥', '♦', '♣', '♠'];
+        Deck::with_rng(n_decks, &mut rand::thread_rng())
+    }
 
-        let card_names: [&str; 13] = [
-            "ACE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT", "NINE", "TEN", "JACK",
-            "QUEEN", "KING",
-        ];
+    /// Creates a shuffled `Deck` whose seed is drawn from the given `rng`,
+    /// so the same generator state always produces the same cards.
+    pub fn with_rng<R: Rng>(n_decks: u8, rng: &mut R) -> Deck {
+        Deck::from_seed(n_decks, rng.gen())
+    }
 
-        let mut deck: Vec<Card> = Vec::new();
+    /// Creates a `Deck` shuffled deterministically from `seed`.
+    /// Later reshuffles are derived from the same seed too.
+    pub fn from_seed(n_decks: u8, seed: u64) -> Deck {
+        let mut cards: Vec<Card> = Vec::new();
         for _ in 0..n_decks {
-            for &suit in &suits {
-                for &card_name in &card_names {
-                    deck.push(Card {
-                        name: card_name.to_string(),
-                        suit,
-                    });
+            for suit in Suit::iter() {
+                for rank in Rank::iter() {
+                    cards.push(Card::new(rank, suit));
                 }
             }
         }
-        let mut rng = rand::thread_rng();
-        deck.shuffle(&mut rng);
+        let n_cards: usize = cards.len();
+        let mut deck = Deck {
+            cards,
+            discards: Vec::new(),
+            cut_card: 0,
+            n_cards,
+            emergency_shuffle: None,
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+        };
+        deck.set_penetration(DEFAULT_PENETRATION);
+        deck.cards.shuffle(&mut deck.rng);
+        deck
+    }
+
+    /// A `Deck` dealing the given cards in order, then reshuffling whatever was discarded.
+    #[cfg(test)]
+    pub(crate) fn stacked(cards: &[Card]) -> Deck {
+        Deck {
+            cards: cards.iter().rev().cloned().collect(),
+            discards: Vec::new(),
+            cut_card: 0,
+            n_cards: cards.len(),
+            emergency_shuffle: None,
+            rng: StdRng::seed_from_u64(0),
+            seed: 0,
+        }
+    }
+
+    /// The seed used to shuffle the deck, needed to replay a session.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Places the cut card so that `penetration` (between 0 and 1)
+    /// of the shoe is dealt before reshuffling.
+    pub fn set_penetration(&mut self, penetration: f64) {
+        let penetration: f64 = penetration.clamp(0.0, 1.0);
+        let dealt: usize = (self.n_cards as f64 * penetration).round() as usize;
+        self.cut_card = self.n_cards - dealt;
+    }
+
+    /// Number of cards left in the shoe.
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// The cards left in the shoe, the next one to be dealt last.
+    pub fn undealt_cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// Number of cards in the discard tray.
+    pub fn discarded(&self) -> usize {
+        self.discards.len()
+    }
 
-        Deck { cards: deck }
+    /// Whether the cut card has been reached, so the shoe should be shuffled
+    /// before the next round.
+    pub fn needs_shuffle(&self) -> bool {
+        self.cards.len() <= self.cut_card
     }
 
+    /// Puts the discard tray back into the shoe and shuffles it.
+    /// Cards still on the table are left out, so it must be called between rounds.
+    pub fn shuffle(&mut self) {
+        self.cards.append(&mut self.discards);
+        self.cards.shuffle(&mut self.rng);
+    }
+
+    /// Moves cards that have left play to the discard tray.
+    pub fn discard(&mut self, cards: &[Card]) {
+        self.discards.extend_from_slice(cards);
+    }
+
+    /// Deals the top card of the shoe. If the shoe runs out in the middle of a round,
+    /// the discard tray is shuffled back in, which is reported by `take_emergency_shuffle`.
     pub fn deal_card(&mut self) -> Card {
-        self.cards.pop().expect("DECK EMPTY, GAME OVER!")
+        if self.cards.is_empty() {
+            *self.emergency_shuffle.get_or_insert(0) += self.discards.len();
+            self.shuffle();
+        }
+        self.cards
+            .pop()
+            .expect("Every card of the shoe is on the table.")
+    }
+
+    /// The number of discarded cards shuffled back in since the last call because the shoe
+    /// ran out in the middle of a round, if it did.
+    pub fn take_emergency_shuffle(&mut self) -> Option<usize> {
+        self.emergency_shuffle.take()
+    }
+
+    pub fn get_initial_cards(&mut self) -> [Card; 2] {
+        [self.deal_card(), self.deal_card()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deal_all(deck: &mut Deck) -> Vec<Card> {
+        (0..deck.remaining()).map(|_| deck.deal_card()).collect()
+    }
+
+    #[test]
+    fn same_seed_deals_same_cards() {
+        let mut first = Deck::from_seed(6, 42);
+        let mut second = Deck::from_seed(6, 42);
+
+        assert_eq!(deal_all(&mut first), deal_all(&mut second));
     }
 
-    pub fn get_initial_cards(&mut self) -> Vec<Card> {
-        vec![self.deal_card(), self.deal_card()]
+    #[test]
+    fn same_seed_reshuffles_the_same_way() {
+        let mut first = Deck::from_seed(2, 7);
+        let mut second = Deck::from_seed(2, 7);
+        for deck in [&mut first, &mut second].iter_mut() {
+            let cards: Vec<Card> = deal_all(deck);
+            deck.discard(&cards);
+            deck.shuffle();
+        }
+
+        assert_eq!(deal_all(&mut first), deal_all(&mut second));
+    }
+
+    #[test]
+    fn different_seeds_deal_different_cards() {
+        let mut first = Deck::from_seed(1, 1);
+        let mut second = Deck::from_seed(1, 2);
+
+        assert_ne!(deal_all(&mut first), deal_all(&mut second));
+    }
+
+    #[test]
+    fn every_card_is_in_the_shoe() {
+        let mut cards: Vec<Card> = deal_all(&mut Deck::from_seed(1, 3));
+        cards.sort();
+        let mut expected: Vec<Card> = Rank::iter()
+            .flat_map(|rank| Suit::iter().map(move |suit| Card::new(rank, suit)))
+            .collect();
+        expected.sort();
+
+        assert_eq!(cards, expected);
+    }
+
+    #[test]
+    fn every_card_parses_back_from_its_display() {
+        for rank in Rank::iter() {
+            for suit in Suit::iter() {
+                let card = Card::new(rank, suit);
+                assert_eq!(card.to_string().parse(), Ok(card));
+            }
+        }
+    }
+
+    #[test]
+    fn cards_parse_from_short_forms() {
+        assert_eq!("10♠".parse(), Ok(Card::new(Rank::Ten, Suit::Spades)));
+        assert_eq!("AH".parse(), Ok(Card::new(Rank::Ace, Suit::Hearts)));
+        assert_eq!(" kd ".parse(), Ok(Card::new(Rank::King, Suit::Diamonds)));
+        assert_eq!("TC".parse(), Ok(Card::new(Rank::Ten, Suit::Clubs)));
+    }
+
+    #[test]
+    fn invalid_cards_are_rejected() {
+        for input in ["", "A", "11H", "AX", "ACE of"].iter() {
+            assert_eq!(input.parse::<Card>(), Err(ParseCardError::new(input)));
+        }
+    }
+
+    #[test]
+    fn ranks_and_suits_parse_from_names_and_symbols() {
+        assert_eq!("queen".parse(), Ok(Rank::Queen));
+        assert_eq!("J".parse(), Ok(Rank::Jack));
+        assert!("0".parse::<Rank>().is_err());
+        assert_eq!("♣".parse(), Ok(Suit::Clubs));
+        assert_eq!("Diamonds".parse(), Ok(Suit::Diamonds));
+        assert!("X".parse::<Suit>().is_err());
+    }
+
+    #[test]
+    fn cut_card_is_reached_at_the_penetration() {
+        let mut deck = Deck::from_seed(1, 0);
+        deck.set_penetration(0.5);
+        for _ in 0..25 {
+            deck.deal_card();
+        }
+        assert!(!deck.needs_shuffle());
+        deck.deal_card();
+        assert!(deck.needs_shuffle());
+
+        let mut deck = Deck::from_seed(2, 0);
+        for _ in 0..77 {
+            deck.deal_card();
+        }
+        assert!(!deck.needs_shuffle());
+        deck.deal_card();
+        assert!(deck.needs_shuffle());
+    }
+
+    #[test]
+    fn penetration_is_clamped() {
+        let mut deck = Deck::from_seed(1, 0);
+        deck.set_penetration(2.0);
+        for _ in 0..51 {
+            deck.deal_card();
+        }
+        assert!(!deck.needs_shuffle());
+
+        deck.set_penetration(-1.0);
+        assert!(deck.needs_shuffle());
+    }
+
+    #[test]
+    fn running_out_shuffles_the_discards_back_in() {
+        let mut deck = Deck::from_seed(1, 0);
+        let cards: Vec<Card> = deal_all(&mut deck);
+        assert_eq!(deck.take_emergency_shuffle(), None);
+        deck.discard(&cards[..10]);
+
+        deck.deal_card();
+        assert_eq!(deck.remaining(), 9);
+        assert_eq!(deck.take_emergency_shuffle(), Some(10));
+        assert_eq!(deck.take_emergency_shuffle(), None);
     }
 }
@@ -0,0 +1,1156 @@
+use crate::deck::{Card, Deck, Rank};
+use crate::hand::{Hand, HandState, Wager};
+use crate::player::{ActionError, Player};
+use crate::rules::{Surrender, TableRules};
+
+use std::fmt;
+use std::mem;
+
+/// A decision a player can make on the hand whose turn it is.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::Hit => "hit",
+            Action::Stand => "stand",
+            Action::Double => "double",
+            Action::Split => "split",
+            Action::Surrender => "surrender",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a hand ended against the dealer.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Outcome {
+    /// The hand won, with the money earned.
+    Won(u32),
+    /// The hand lost, with the money lost.
+    Lost(u32),
+    Tied,
+}
+
+/// Something that happened during a round, reported so a front-end can show it.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The cut card was reached and the discard tray was shuffled back into the shoe.
+    Shuffled {
+        discarded: usize,
+        remaining: usize,
+    },
+    /// The shoe ran out in the middle of the round, so the `discarded` cards were shuffled
+    /// back in.
+    ShoeRanOut {
+        discarded: usize,
+    },
+    DealerUpcard(Card),
+    /// The dealer may have a blackjack, so the players can surrender before the dealer
+    /// checks the hole card (early surrender).
+    SurrenderOffered,
+    /// The dealer shows an ace, so the players can insure their bets.
+    InsuranceOffered,
+    Insured {
+        player: usize,
+        money: u32,
+    },
+    EvenMoney {
+        player: usize,
+    },
+    /// The dealer checked the hole card for blackjack.
+    DealerPeeked {
+        blackjack: bool,
+    },
+    /// It is the turn of a new player, who was dealt `cards`.
+    TurnStarted {
+        player: usize,
+        cards: Hand,
+    },
+    Hit {
+        player: usize,
+        hand: usize,
+        cards: Hand,
+    },
+    Stood {
+        player: usize,
+        hand: usize,
+    },
+    Doubled {
+        player: usize,
+        hand: usize,
+        cards: Hand,
+    },
+    /// The hand was split in the `first` and `second` hands.
+    Split {
+        player: usize,
+        hand: usize,
+        first: Hand,
+        second: Hand,
+    },
+    Surrendered {
+        player: usize,
+        hand: usize,
+    },
+    Blackjack {
+        player: usize,
+        hand: usize,
+    },
+    TwentyOne {
+        player: usize,
+        hand: usize,
+    },
+    Bust {
+        player: usize,
+        hand: usize,
+    },
+    DealerRevealed(Hand),
+    DealerHit(Hand),
+    DealerBust,
+    /// Every player has played, the hands are about to be settled.
+    RoundFinished,
+    InsuranceSettled {
+        player: usize,
+        hand: usize,
+        outcome: Outcome,
+    },
+    Settled {
+        player: usize,
+        hand: usize,
+        outcome: Outcome,
+    },
+}
+
+/// The stage of the round the game is in.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Phase {
+    /// Players are placing their bets before the cards are dealt.
+    Betting,
+    /// The dealer may have a blackjack and the players can surrender before the peek.
+    Surrender,
+    /// The dealer shows an ace and the players can take insurance or even money.
+    Insurance,
+    /// The given hand of the given player has to make a decision.
+    PlayerTurn { player: usize, hand: usize },
+    /// The dealer has played and every hand has been settled.
+    Finished,
+}
+
+/// A round-based blackjack table, driven by the players' bets and actions.
+///
+/// Every call records what happened as `Event`s, available through `take_events`.
+pub struct Game {
+    rules: TableRules,
+    deck: Deck,
+    players: Vec<Player>,
+    dealer_hand: Hand,
+    phase: Phase,
+    events: Vec<Event>,
+}
+
+impl Game {
+    /// Creates a new `Game` with no players, given the `rules` of the table
+    /// and the `deck` to deal from.
+    pub fn new(rules: TableRules, deck: Deck) -> Game {
+        Game {
+            rules,
+            deck,
+            players: Vec::new(),
+            dealer_hand: Hand::from_cards(&[]),
+            phase: Phase::Betting,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &TableRules {
+        &self.rules
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn dealer_hand(&self) -> &Hand {
+        &self.dealer_hand
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Returns the `(player, hand)` indexes of the hand that has to make a decision, if any.
+    pub fn turn(&self) -> Option<(usize, usize)> {
+        match self.phase {
+            Phase::PlayerTurn { player, hand } => Some((player, hand)),
+            _ => None,
+        }
+    }
+
+    /// The decisions the given hand can make right now, empty if it is not its turn.
+    pub fn available_actions(&self, player: usize, hand: usize) -> Vec<Action> {
+        if self.turn() != Some((player, hand)) {
+            return Vec::new();
+        }
+        let rules: &TableRules = &self.rules;
+        let player: &Player = &self.players[player];
+        let mut actions: Vec<Action> = Vec::new();
+
+        if player.can_hit(hand, rules).is_ok() {
+            actions.push(Action::Hit);
+        }
+        actions.push(Action::Stand);
+        if player.can_split(hand, rules).is_ok() {
+            actions.push(Action::Split);
+        }
+        if player.can_double(hand, rules).is_ok() {
+            actions.push(Action::Double);
+        }
+        if player.can_surrender(rules).is_ok() {
+            actions.push(Action::Surrender);
+        }
+        actions
+    }
+
+    /// Removes and returns the events recorded since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        mem::take(&mut self.events)
+    }
+
+    /// Sits a new player at the table. Should be called between rounds.
+    pub fn add_player(&mut self, name: String, initial_money: u32) {
+        self.players.push(Player::new(name, initial_money));
+    }
+
+    /// Keeps only the players for whom `keep` returns `true`.
+    /// Should be called between rounds.
+    pub fn retain_players<F: FnMut(&Player) -> bool>(&mut self, keep: F) {
+        self.players.retain(keep);
+    }
+
+    /// Sets the bet of the given player for the next round, return the reason why
+    /// if the bet is not allowed.
+    pub fn place_bet(&mut self, player: usize, money: u32) -> Result<(), ActionError> {
+        if self.phase != Phase::Betting {
+            Err(ActionError::NotOffered)
+        } else if money > self.players[player].actual_money {
+            Err(ActionError::InsufficientFunds)
+        } else if money < self.rules.min_bet || money > self.rules.max_bet {
+            Err(ActionError::BetOutOfLimits)
+        } else {
+            self.players[player].bet(money);
+            Ok(())
+        }
+    }
+
+    /// Deals the initial cards to every player and the dealer, shuffling the shoe first
+    /// if the cut card has been reached. Return the reason why if they cannot be dealt.
+    pub fn deal(&mut self) -> Result<(), ActionError> {
+        if self.phase != Phase::Betting {
+            return Err(ActionError::NotOffered);
+        } else if self.players.is_empty() {
+            return Err(ActionError::NoPlayers);
+        } else if self.players.iter().any(|player| player.bet == 0) {
+            return Err(ActionError::MissingBet);
+        }
+
+        if self.deck.needs_shuffle() {
+            self.events.push(Event::Shuffled {
+                discarded: self.deck.discarded(),
+                remaining: self.deck.remaining(),
+            });
+            self.deck.shuffle();
+        }
+        for player in self.players.iter_mut() {
+            player.reset_hands(&mut self.deck);
+        }
+        self.dealer_hand = if self.rules.dealer_peeks {
+            Hand::new(&mut self.deck)
+        } else {
+            Hand::from_cards(&[self.deck.deal_card()])
+        };
+        self.report_emergency_shuffle();
+        self.events
+            .push(Event::DealerUpcard(self.dealer_hand.cards()[0]));
+
+        if self.rules.surrender == Surrender::Early && self.dealer_peeks() {
+            self.phase = Phase::Surrender;
+            self.events.push(Event::SurrenderOffered);
+        } else {
+            self.offer_insurance();
+        }
+        Ok(())
+    }
+
+    /// Gives up the hand of the given player before the dealer checks for blackjack,
+    /// return the reason why if it is not allowed.
+    pub fn surrender(&mut self, player: usize) -> Result<(), ActionError> {
+        if self.phase != Phase::Surrender {
+            return Err(ActionError::NotOffered);
+        }
+        self.players[player].surrender(&self.rules)?;
+        self.events.push(Event::Surrendered { player, hand: 0 });
+        Ok(())
+    }
+
+    /// Stops offering early surrender, going on with the insurance or the players' turns.
+    pub fn close_surrender(&mut self) -> Result<(), ActionError> {
+        if self.phase != Phase::Surrender {
+            return Err(ActionError::NotOffered);
+        }
+        self.offer_insurance();
+        Ok(())
+    }
+
+    /// Places an insurance of up to half the bet of the given player,
+    /// return the reason why if it is not allowed.
+    pub fn insure(&mut self, player: usize, money: u32) -> Result<(), ActionError> {
+        if self.phase != Phase::Insurance {
+            return Err(ActionError::NotOffered);
+        }
+        self.players[player].insure(money)?;
+        self.events.push(Event::Insured { player, money });
+        Ok(())
+    }
+
+    /// Pays the blackjack of the given player 1:1 whatever the dealer has,
+    /// return the reason why if it is not allowed.
+    pub fn take_even_money(&mut self, player: usize) -> Result<(), ActionError> {
+        if self.phase != Phase::Insurance {
+            return Err(ActionError::NotOffered);
+        }
+        self.players[player].take_even_money()?;
+        self.events.push(Event::EvenMoney { player });
+        Ok(())
+    }
+
+    /// Stops offering insurance and gives the turn to the first player.
+    pub fn close_insurance(&mut self) -> Result<(), ActionError> {
+        if self.phase != Phase::Insurance {
+            return Err(ActionError::NotOffered);
+        }
+        self.start_players_turns();
+        Ok(())
+    }
+
+    /// Performs the `action` on the hand whose turn it is,
+    /// return the reason why if it is not allowed.
+    pub fn act(&mut self, action: Action) -> Result<(), ActionError> {
+        let (player, hand) = self.turn().ok_or(ActionError::NotOffered)?;
+
+        match action {
+            Action::Hit => {
+                self.players[player].hit(&mut self.deck, hand, &self.rules)?;
+                self.report_emergency_shuffle();
+                self.events.push(Event::Hit {
+                    player,
+                    hand,
+                    cards: self.players[player].hands[hand].clone(),
+                });
+                self.advance_turn(player, hand);
+            }
+            Action::Stand => {
+                self.players[player].hands[hand].state = HandState::Stood;
+                self.events.push(Event::Stood { player, hand });
+                self.advance_turn(player, hand + 1);
+            }
+            Action::Double => {
+                self.players[player].double(hand, &self.rules)?;
+                // A doubled hand only gets one more card
+                self.players[player].hands[hand].deal_card(&mut self.deck);
+                self.report_emergency_shuffle();
+                self.players[player].hands[hand].state = HandState::Stood;
+                self.events.push(Event::Doubled {
+                    player,
+                    hand,
+                    cards: self.players[player].hands[hand].clone(),
+                });
+                self.hand_is_finished(player, hand);
+                self.advance_turn(player, hand + 1);
+            }
+            Action::Split => {
+                self.players[player].split(&mut self.deck, hand, &self.rules)?;
+                self.report_emergency_shuffle();
+                let hands: &[Hand] = &self.players[player].hands;
+                self.events.push(Event::Split {
+                    player,
+                    hand,
+                    first: hands[hand].clone(),
+                    second: hands[hand + 1].clone(),
+                });
+                self.advance_turn(player, hand);
+            }
+            Action::Surrender => {
+                self.players[player].surrender(&self.rules)?;
+                self.events.push(Event::Surrendered { player, hand });
+                self.advance_turn(player, hand + 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves every card on the table to the discard tray and waits for the bets
+    /// of the next round.
+    pub fn next_round(&mut self) {
+        for player in self.players.iter_mut() {
+            player.discard_hands(&mut self.deck);
+            player.bet(0);
+        }
+        self.deck.discard(self.dealer_hand.cards());
+        self.dealer_hand = Hand::from_cards(&[]);
+        self.phase = Phase::Betting;
+    }
+
+    /// Records an event if the shoe ran out while dealing and the discards were shuffled back in.
+    fn report_emergency_shuffle(&mut self) {
+        if let Some(discarded) = self.deck.take_emergency_shuffle() {
+            self.events.push(Event::ShoeRanOut { discarded });
+        }
+    }
+
+    /// Whether the dealer checks the hole card for blackjack, which happens
+    /// if the upcard is an ace or worth ten.
+    fn dealer_peeks(&self) -> bool {
+        let upcard: Card = self.dealer_hand.cards()[0];
+        self.rules.dealer_peeks && (upcard.rank() == Rank::Ace || upcard.value() == 10)
+    }
+
+    /// Offers insurance if the dealer shows an ace, otherwise the players' turns start.
+    fn offer_insurance(&mut self) {
+        if self.dealer_hand.cards()[0].rank() == Rank::Ace {
+            self.phase = Phase::Insurance;
+            self.events.push(Event::InsuranceOffered);
+        } else {
+            self.start_players_turns();
+        }
+    }
+
+    /// Lets the dealer peek for blackjack if the upcard is an ace or worth ten,
+    /// ending the round right away if there is one. Otherwise, the first player plays.
+    fn start_players_turns(&mut self) {
+        if self.dealer_peeks() {
+            let blackjack: bool = self.dealer_hand.has_blackjack();
+            self.events.push(Event::DealerPeeked { blackjack });
+            if blackjack {
+                self.events
+                    .push(Event::DealerRevealed(self.dealer_hand.clone()));
+                self.settle();
+                return;
+            }
+        }
+        self.start_turn(0);
+    }
+
+    fn start_turn(&mut self, player: usize) {
+        if let Some(first_player) = self.players.get(player) {
+            self.events.push(Event::TurnStarted {
+                player,
+                cards: first_player.hands[0].clone(),
+            });
+        }
+        self.advance_turn(player, 0);
+    }
+
+    /// Gives the turn to the first hand, from the given one onwards, that needs a decision.
+    /// If there is none left, the dealer plays and the round is settled.
+    fn advance_turn(&mut self, player: usize, hand: usize) {
+        if player >= self.players.len() {
+            self.dealer_turn();
+            self.settle();
+        } else if hand >= self.players[player].hands.len() {
+            self.start_turn(player + 1);
+        } else if self.hand_is_finished(player, hand) {
+            self.advance_turn(player, hand + 1);
+        } else {
+            self.phase = Phase::PlayerTurn { player, hand };
+        }
+    }
+
+    /// Whether the hand cannot take more cards, recording why.
+    /// Split aces that cannot take more cards are finished too, unless they can be split again.
+    fn hand_is_finished(&mut self, player: usize, hand: usize) -> bool {
+        let hands: &[Hand] = &self.players[player].hands;
+        let cards: &Hand = &hands[hand];
+        let event = if cards.is_surrendered() {
+            return true;
+        } else if cards.has_blackjack() {
+            Event::Blackjack { player, hand }
+        } else if cards.points() == 21 {
+            Event::TwentyOne { player, hand }
+        } else if cards.is_bust() {
+            Event::Bust { player, hand }
+        } else {
+            let can_resplit: bool = cards.is_pair()
+                && self.rules.resplit_aces
+                && hands.len() < usize::from(self.rules.max_split_hands);
+            return cards.is_split_aces() && !self.rules.hit_split_aces && !can_resplit;
+        };
+        self.events.push(event);
+        true
+    }
+
+    fn dealer_turn(&mut self) {
+        if !self.rules.dealer_peeks {
+            self.dealer_hand.deal_card(&mut self.deck);
+            self.report_emergency_shuffle();
+        }
+        self.events
+            .push(Event::DealerRevealed(self.dealer_hand.clone()));
+        while self.rules.dealer_hits(self.dealer_hand.value()) {
+            self.dealer_hand.deal_card(&mut self.deck);
+            self.report_emergency_shuffle();
+            self.events.push(Event::DealerHit(self.dealer_hand.clone()));
+        }
+        if self.dealer_hand.is_bust() {
+            self.events.push(Event::DealerBust);
+        }
+    }
+
+    fn settle(&mut self) {
+        self.events.push(Event::RoundFinished);
+
+        for (player_index, player) in self.players.iter_mut().enumerate() {
+            for hand_index in 0..player.hands.len() {
+                let hand: &Hand = &player.hands[hand_index];
+
+                if let Some(outcome) = insurance_outcome(hand, &self.dealer_hand) {
+                    pay_outcome(player, outcome);
+                    self.events.push(Event::InsuranceSettled {
+                        player: player_index,
+                        hand: hand_index,
+                        outcome,
+                    });
+                }
+
+                let outcome: Outcome =
+                    hand_outcome(&player.hands[hand_index], &self.dealer_hand, &self.rules);
+                pay_outcome(player, outcome);
+                self.events.push(Event::Settled {
+                    player: player_index,
+                    hand: hand_index,
+                    outcome,
+                });
+            }
+        }
+        self.phase = Phase::Finished;
+    }
+}
+
+/// Settles the main bet of a finished `hand` against the dealer's final hand.
+fn hand_outcome(hand: &Hand, dealer_hand: &Hand, rules: &TableRules) -> Outcome {
+    let wager: &Wager = &hand.wager;
+    let bet: u32 = wager.amount();
+
+    if wager.even_money {
+        Outcome::Won(wager.base)
+    } else if hand.is_surrendered() {
+        // Late surrender does not save the bet from a blackjack the dealer did not peek for
+        if rules.surrender == Surrender::Late && dealer_hand.has_blackjack() {
+            Outcome::Lost(bet)
+        } else {
+            // The half of an odd bet is rounded down, in favour of the player
+            Outcome::Lost(bet / 2)
+        }
+    } else if hand.is_bust() {
+        Outcome::Lost(bet)
+    } else if dealer_hand.has_blackjack() {
+        if hand.has_blackjack() {
+            Outcome::Tied
+        } else {
+            Outcome::Lost(bet)
+        }
+    } else if hand.has_blackjack() {
+        Outcome::Won(rules.blackjack_winnings(bet))
+    } else if dealer_hand.is_bust() || hand.points() > dealer_hand.points() {
+        Outcome::Won(bet)
+    } else if hand.points() < dealer_hand.points() {
+        Outcome::Lost(bet)
+    } else {
+        Outcome::Tied
+    }
+}
+
+/// Settles the insurance of the `hand` at 2:1, if it was insured.
+fn insurance_outcome(hand: &Hand, dealer_hand: &Hand) -> Option<Outcome> {
+    let insurance: u32 = hand.wager.insurance;
+
+    if insurance == 0 {
+        None
+    } else if dealer_hand.has_blackjack() {
+        Some(Outcome::Won(insurance * 2))
+    } else {
+        Some(Outcome::Lost(insurance))
+    }
+}
+
+fn pay_outcome(player: &mut Player, outcome: Outcome) {
+    match outcome {
+        Outcome::Won(money) => player.collect(money),
+        Outcome::Lost(money) => player.pay(money),
+        Outcome::Tied => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+
+    fn hand(ranks: &[Rank], bet: u32) -> Hand {
+        let cards: Vec<Card> = ranks
+            .iter()
+            .map(|&rank| Card::new(rank, Suit::Hearts))
+            .collect();
+        let mut hand = Hand::from_cards(&cards);
+        hand.wager.base = bet;
+        hand
+    }
+
+    fn split_hand(ranks: &[Rank], bet: u32) -> Hand {
+        let mut hand = hand(ranks, bet);
+        hand.split = true;
+        hand
+    }
+
+    fn surrendered(ranks: &[Rank], bet: u32) -> Hand {
+        let mut hand = hand(ranks, bet);
+        hand.state = HandState::Surrendered;
+        hand
+    }
+
+    fn dealer(ranks: &[Rank]) -> Hand {
+        hand(ranks, 0)
+    }
+
+    fn dealer_blackjack() -> Hand {
+        dealer(&[Rank::Ace, Rank::King])
+    }
+
+    #[test]
+    fn blackjack_is_paid_by_the_table_payout() {
+        let mut rules = TableRules::default();
+        let blackjack: Hand = hand(&[Rank::Ace, Rank::Queen], 10);
+        let twenty: Hand = dealer(&[Rank::King, Rank::Ten]);
+
+        assert_eq!(hand_outcome(&blackjack, &twenty, &rules), Outcome::Won(15));
+        rules.blackjack_payout = "6:5".parse().unwrap();
+        assert_eq!(hand_outcome(&blackjack, &twenty, &rules), Outcome::Won(12));
+        assert_eq!(
+            hand_outcome(&blackjack, &dealer_blackjack(), &rules),
+            Outcome::Tied
+        );
+    }
+
+    #[test]
+    fn split_twenty_one_is_not_a_blackjack() {
+        let rules = TableRules::default();
+        let twenty_one: Hand = split_hand(&[Rank::Ace, Rank::King], 10);
+
+        assert_eq!(
+            hand_outcome(&twenty_one, &dealer(&[Rank::King, Rank::Ten]), &rules),
+            Outcome::Won(10)
+        );
+        assert_eq!(
+            hand_outcome(&twenty_one, &dealer_blackjack(), &rules),
+            Outcome::Lost(10)
+        );
+    }
+
+    #[test]
+    fn doubled_hand_wins_and_loses_twice_the_bet() {
+        let rules = TableRules::default();
+        let mut doubled: Hand = hand(&[Rank::Five, Rank::Six, Rank::Nine], 10);
+        doubled.wager.doubled = true;
+
+        assert_eq!(
+            hand_outcome(&doubled, &dealer(&[Rank::Ten, Rank::Eight]), &rules),
+            Outcome::Won(20)
+        );
+        assert_eq!(
+            hand_outcome(&doubled, &dealer(&[Rank::Ten, Rank::Queen]), &rules),
+            Outcome::Tied
+        );
+        // Without a hole card, the dealer's blackjack is only found after doubling
+        let enhc = TableRules {
+            dealer_peeks: false,
+            ..TableRules::default()
+        };
+        assert_eq!(
+            hand_outcome(&doubled, &dealer_blackjack(), &enhc),
+            Outcome::Lost(20)
+        );
+    }
+
+    #[test]
+    fn bust_loses_even_if_the_dealer_busts() {
+        let rules = TableRules::default();
+        let bust: Hand = hand(&[Rank::Ten, Rank::Six, Rank::Eight], 10);
+
+        assert_eq!(
+            hand_outcome(&bust, &dealer(&[Rank::Ten, Rank::Six, Rank::Nine]), &rules),
+            Outcome::Lost(10)
+        );
+    }
+
+    #[test]
+    fn surrender_loses_half_of_the_bet() {
+        let rules = TableRules::default();
+        let sixteen: Hand = surrendered(&[Rank::Ten, Rank::Six], 10);
+
+        assert_eq!(
+            hand_outcome(&sixteen, &dealer(&[Rank::Ten, Rank::Seven]), &rules),
+            Outcome::Lost(5)
+        );
+    }
+
+    #[test]
+    fn late_surrender_without_hole_card_loses_the_bet_to_a_blackjack() {
+        let rules = TableRules {
+            dealer_peeks: false,
+            surrender: Surrender::Late,
+            ..TableRules::default()
+        };
+        let sixteen: Hand = surrendered(&[Rank::Ten, Rank::Six], 10);
+
+        assert_eq!(
+            hand_outcome(&sixteen, &dealer_blackjack(), &rules),
+            Outcome::Lost(10)
+        );
+    }
+
+    #[test]
+    fn early_surrender_saves_half_of_the_bet_from_a_blackjack() {
+        let rules = TableRules {
+            surrender: Surrender::Early,
+            ..TableRules::default()
+        };
+        let sixteen: Hand = surrendered(&[Rank::Ten, Rank::Six], 10);
+
+        assert_eq!(
+            hand_outcome(&sixteen, &dealer_blackjack(), &rules),
+            Outcome::Lost(5)
+        );
+    }
+
+    #[test]
+    fn even_money_wins_the_bet_whatever_the_dealer_has() {
+        let rules = TableRules::default();
+        let mut blackjack: Hand = hand(&[Rank::Ace, Rank::Jack], 10);
+        blackjack.wager.even_money = true;
+
+        assert_eq!(
+            hand_outcome(&blackjack, &dealer_blackjack(), &rules),
+            Outcome::Won(10)
+        );
+        assert_eq!(
+            hand_outcome(&blackjack, &dealer(&[Rank::Ace, Rank::Six]), &rules),
+            Outcome::Won(10)
+        );
+    }
+
+    #[test]
+    fn insurance_pays_two_to_one() {
+        let mut insured: Hand = hand(&[Rank::Ten, Rank::Nine], 10);
+        assert_eq!(insurance_outcome(&insured, &dealer_blackjack()), None);
+
+        insured.wager.insurance = 5;
+        assert_eq!(
+            insurance_outcome(&insured, &dealer_blackjack()),
+            Some(Outcome::Won(10))
+        );
+        assert_eq!(
+            insurance_outcome(&insured, &dealer(&[Rank::Ace, Rank::Nine])),
+            Some(Outcome::Lost(5))
+        );
+    }
+
+    #[test]
+    fn surrender_of_an_odd_bet_loses_half_rounded_down() {
+        let rules = TableRules::default();
+
+        assert_eq!(
+            hand_outcome(
+                &surrendered(&[Rank::Ten, Rank::Six], 15),
+                &dealer(&[Rank::Ten, Rank::Seven]),
+                &rules
+            ),
+            Outcome::Lost(7)
+        );
+        assert_eq!(
+            hand_outcome(
+                &surrendered(&[Rank::Ten, Rank::Six], 1),
+                &dealer(&[Rank::Ten, Rank::Seven]),
+                &rules
+            ),
+            Outcome::Lost(0)
+        );
+    }
+
+    #[test]
+    fn any_bet_within_the_limits_and_the_money_is_allowed() {
+        let mut game = Game::new(TableRules::default(), Deck::from_seed(1, 0));
+        game.add_player(String::from("Player"), 1);
+        assert_eq!(game.place_bet(0, 2), Err(ActionError::InsufficientFunds));
+        assert_eq!(game.place_bet(0, 1), Ok(()));
+    }
+
+    /// A game whose only player holds the `hands` against the `dealer_hand`, ready to settle.
+    fn game_with(hands: Vec<Hand>, dealer_hand: Hand, rules: TableRules) -> Game {
+        let mut game = Game::new(rules, Deck::from_seed(1, 0));
+        game.add_player(String::from("Player"), 100);
+        game.players[0].hands = hands;
+        game.dealer_hand = dealer_hand;
+        game
+    }
+
+    fn settlements(game: &mut Game) -> Vec<Event> {
+        game.take_events()
+            .into_iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    Event::InsuranceSettled { .. } | Event::Settled { .. }
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insured_hand_breaks_even_against_a_blackjack() {
+        let mut insured: Hand = hand(&[Rank::Ten, Rank::Nine], 10);
+        insured.wager.insurance = 5;
+        let mut game = game_with(vec![insured], dealer_blackjack(), TableRules::default());
+        game.settle();
+
+        let events: Vec<Event> = settlements(&mut game);
+        assert!(matches!(
+            events[0],
+            Event::InsuranceSettled {
+                outcome: Outcome::Won(10),
+                ..
+            }
+        ));
+        assert!(matches!(
+            events[1],
+            Event::Settled {
+                outcome: Outcome::Lost(10),
+                ..
+            }
+        ));
+        assert_eq!(game.players()[0].actual_money, 100);
+        assert_eq!(game.phase(), Phase::Finished);
+    }
+
+    #[test]
+    fn dealer_blackjack_found_on_the_peek_ends_the_round() {
+        let mut game = game_with(
+            vec![hand(&[Rank::Ten, Rank::Nine], 10)],
+            dealer(&[Rank::King, Rank::Ace]),
+            TableRules::default(),
+        );
+        game.start_players_turns();
+
+        let events: Vec<Event> = game.take_events();
+        assert!(matches!(events[0], Event::DealerPeeked { blackjack: true }));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, Event::TurnStarted { .. })));
+        assert_eq!(game.phase(), Phase::Finished);
+        assert_eq!(game.turn(), None);
+        assert_eq!(game.players()[0].actual_money, 90);
+    }
+
+    #[test]
+    fn dealer_without_blackjack_lets_the_players_play() {
+        let mut game = game_with(
+            vec![hand(&[Rank::Ten, Rank::Nine], 10)],
+            dealer(&[Rank::King, Rank::Seven]),
+            TableRules::default(),
+        );
+        game.start_players_turns();
+
+        let events: Vec<Event> = game.take_events();
+        assert!(matches!(
+            events[0],
+            Event::DealerPeeked { blackjack: false }
+        ));
+        assert!(matches!(events[1], Event::TurnStarted { player: 0, .. }));
+        assert_eq!(game.turn(), Some((0, 0)));
+    }
+
+    #[test]
+    fn dealer_without_hole_card_draws_the_second_card_after_the_players() {
+        let rules = TableRules {
+            dealer_peeks: false,
+            ..TableRules::default()
+        };
+        let mut game = game_with(
+            vec![hand(&[Rank::Ten, Rank::Nine], 10)],
+            dealer(&[Rank::Ace]),
+            rules,
+        );
+        game.start_players_turns();
+
+        assert!(!game
+            .take_events()
+            .iter()
+            .any(|event| matches!(event, Event::DealerPeeked { .. })));
+        assert_eq!(game.turn(), Some((0, 0)));
+        assert_eq!(game.dealer_hand().cards().len(), 1);
+
+        game.act(Action::Stand).unwrap();
+        assert!(game.dealer_hand().cards().len() >= 2);
+        assert_eq!(game.phase(), Phase::Finished);
+    }
+
+    #[test]
+    fn dealing_without_hole_card_gives_the_dealer_one_card() {
+        for &dealer_peeks in [true, false].iter() {
+            let rules = TableRules {
+                dealer_peeks,
+                ..TableRules::default()
+            };
+            let mut game = Game::new(rules, Deck::from_seed(6, 1));
+            game.add_player(String::from("Player"), 100);
+            game.place_bet(0, 10).unwrap();
+            game.deal().unwrap();
+
+            let dealer_cards: usize = if dealer_peeks { 2 } else { 1 };
+            assert_eq!(game.dealer_hand().cards().len(), dealer_cards);
+        }
+    }
+
+    /// A game where the only player holds a pair of aces and has to play it, dealing the
+    /// `next` cards in order.
+    fn split_aces_game(resplit_aces: bool, hit_split_aces: bool, next: &[Rank]) -> Game {
+        let rules = TableRules {
+            resplit_aces,
+            hit_split_aces,
+            ..TableRules::default()
+        };
+        let mut game = game_with(
+            vec![hand(&[Rank::Ace, Rank::Ace], 10)],
+            dealer(&[Rank::Ten, Rank::Seven]),
+            rules,
+        );
+        let cards: Vec<Card> = next
+            .iter()
+            .map(|&rank| Card::new(rank, Suit::Clubs))
+            .collect();
+        game.deck = Deck::stacked(&cards);
+        game.phase = Phase::PlayerTurn { player: 0, hand: 0 };
+        game
+    }
+
+    fn ranks(hand: &Hand) -> Vec<Rank> {
+        hand.cards().iter().map(|card| card.rank()).collect()
+    }
+
+    #[test]
+    fn split_aces_get_one_card_each() {
+        // The new hand is dealt first
+        let mut game = split_aces_game(false, false, &[Rank::Ace, Rank::Nine]);
+        assert!(game.available_actions(0, 0).contains(&Action::Split));
+        game.act(Action::Split).unwrap();
+
+        let hands: &[Hand] = &game.players()[0].hands;
+        assert_eq!(ranks(&hands[0]), vec![Rank::Ace, Rank::Nine]);
+        assert_eq!(ranks(&hands[1]), vec![Rank::Ace, Rank::Ace]);
+        // Neither hand can take more cards nor split again, so the dealer plays
+        assert_eq!(game.phase(), Phase::Finished);
+    }
+
+    #[test]
+    fn split_aces_can_be_split_again_if_allowed() {
+        let mut game =
+            split_aces_game(true, false, &[Rank::Nine, Rank::Ace, Rank::Five, Rank::Six]);
+        game.act(Action::Split).unwrap();
+
+        assert_eq!(game.turn(), Some((0, 0)));
+        assert_eq!(
+            game.available_actions(0, 0),
+            vec![Action::Stand, Action::Split]
+        );
+        game.act(Action::Split).unwrap();
+
+        let hands: &[Hand] = &game.players()[0].hands;
+        assert_eq!(hands.len(), 3);
+        assert_eq!(ranks(&hands[0]), vec![Rank::Ace, Rank::Six]);
+        assert_eq!(ranks(&hands[1]), vec![Rank::Ace, Rank::Five]);
+        assert_eq!(ranks(&hands[2]), vec![Rank::Ace, Rank::Nine]);
+        assert_eq!(game.phase(), Phase::Finished);
+    }
+
+    #[test]
+    fn split_aces_can_be_hit_if_allowed() {
+        let mut game = split_aces_game(false, true, &[Rank::Ace, Rank::Six, Rank::Two]);
+        game.act(Action::Split).unwrap();
+
+        assert_eq!(game.turn(), Some((0, 0)));
+        assert_eq!(
+            game.available_actions(0, 0),
+            vec![Action::Hit, Action::Stand, Action::Double]
+        );
+        game.act(Action::Hit).unwrap();
+        game.act(Action::Stand).unwrap();
+
+        // A pair of aces again, but it cannot be split
+        assert_eq!(game.turn(), Some((0, 1)));
+        assert_eq!(
+            game.available_actions(0, 1),
+            vec![Action::Hit, Action::Stand, Action::Double]
+        );
+        assert_eq!(
+            game.players()[0].can_split(1, game.rules()),
+            Err(ActionError::ResplitAces)
+        );
+    }
+
+    #[test]
+    fn split_aces_can_be_hit_and_split_again_if_both_are_allowed() {
+        let mut game = split_aces_game(true, true, &[Rank::Ace, Rank::Six]);
+        game.act(Action::Split).unwrap();
+        game.act(Action::Stand).unwrap();
+
+        assert_eq!(game.turn(), Some((0, 1)));
+        assert_eq!(
+            game.available_actions(0, 1),
+            vec![Action::Hit, Action::Stand, Action::Split, Action::Double]
+        );
+    }
+
+    /// A game where the only player has to play a pair of eights, dealing the `next` cards.
+    fn eights_game(next: &[Rank]) -> Game {
+        let mut game = game_with(
+            vec![hand(&[Rank::Eight, Rank::Eight], 10)],
+            dealer(&[Rank::Ten, Rank::Seven]),
+            TableRules::default(),
+        );
+        let cards: Vec<Card> = next
+            .iter()
+            .map(|&rank| Card::new(rank, Suit::Clubs))
+            .collect();
+        game.deck = Deck::stacked(&cards);
+        game.phase = Phase::PlayerTurn { player: 0, hand: 0 };
+        game
+    }
+
+    #[test]
+    fn every_action_is_available_on_the_first_two_cards() {
+        let game: Game = eights_game(&[]);
+
+        assert_eq!(
+            game.available_actions(0, 0),
+            vec![
+                Action::Hit,
+                Action::Stand,
+                Action::Split,
+                Action::Double,
+                Action::Surrender
+            ]
+        );
+    }
+
+    #[test]
+    fn split_double_and_surrender_are_not_available_after_hitting() {
+        let mut game: Game = eights_game(&[Rank::Two]);
+        game.act(Action::Hit).unwrap();
+
+        assert_eq!(game.turn(), Some((0, 0)));
+        assert_eq!(
+            game.available_actions(0, 0),
+            vec![Action::Hit, Action::Stand]
+        );
+    }
+
+    #[test]
+    fn surrender_is_not_available_after_splitting() {
+        let mut game: Game = eights_game(&[Rank::Three, Rank::Two]);
+        game.act(Action::Split).unwrap();
+
+        assert_eq!(
+            game.available_actions(0, 0),
+            vec![Action::Hit, Action::Stand, Action::Double]
+        );
+    }
+
+    #[test]
+    fn no_action_is_available_out_of_turn() {
+        let mut game: Game = eights_game(&[Rank::Three, Rank::Two, Rank::Ten]);
+        game.act(Action::Split).unwrap();
+
+        assert!(game.available_actions(0, 1).is_empty());
+        assert!(game.available_actions(1, 0).is_empty());
+
+        game.act(Action::Stand).unwrap();
+        assert!(game.available_actions(0, 0).is_empty());
+        assert!(!game.available_actions(0, 1).is_empty());
+
+        game.act(Action::Stand).unwrap();
+        assert_eq!(game.phase(), Phase::Finished);
+        assert!(game.available_actions(0, 1).is_empty());
+    }
+
+    #[test]
+    fn running_out_of_cards_is_reported() {
+        let mut game = game_with(
+            vec![hand(&[Rank::Two, Rank::Three], 10)],
+            dealer(&[Rank::Ten, Rank::Seven]),
+            TableRules::default(),
+        );
+        while game.deck.remaining() > 0 {
+            let card: Card = game.deck.deal_card();
+            game.deck.discard(&[card]);
+        }
+        game.phase = Phase::PlayerTurn { player: 0, hand: 0 };
+        game.act(Action::Hit).unwrap();
+
+        let events: Vec<Event> = game.take_events();
+        assert!(matches!(events[0], Event::ShoeRanOut { discarded: 52 }));
+        assert!(matches!(events[1], Event::Hit { .. }));
+    }
+
+    #[test]
+    fn every_split_hand_is_settled_on_its_own() {
+        let mut doubled: Hand = split_hand(&[Rank::Eight, Rank::Three, Rank::Ten], 10);
+        doubled.wager.doubled = true;
+        let hands: Vec<Hand> = vec![
+            doubled,
+            split_hand(&[Rank::Eight, Rank::Ten], 10),
+            split_hand(&[Rank::Eight, Rank::Six, Rank::Nine], 10),
+        ];
+        let mut game = game_with(
+            hands,
+            dealer(&[Rank::Ten, Rank::Nine]),
+            TableRules::default(),
+        );
+        game.settle();
+
+        let outcomes: Vec<(usize, Outcome)> = settlements(&mut game)
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Settled { hand, outcome, .. } => Some((hand, outcome)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, Outcome::Won(20)),
+                (1, Outcome::Lost(10)),
+                (2, Outcome::Lost(10)),
+            ]
+        );
+        assert_eq!(game.players()[0].actual_money, 100);
+    }
+}
@@ -1,70 +1,218 @@
-use crate::deck::{Card, Deck};
+use crate::deck::{Card, Deck, Rank, Suit};
 
 use std::fmt;
 
+/// The total of a `Hand`, telling whether an ace is being counted as 11 points.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HandValue {
+    Hard(u8),
+    Soft(u8),
+}
+
+impl fmt::Display for HandValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandValue::Hard(total) => write!(f, "{}", total),
+            HandValue::Soft(total) => write!(f, "soft {}", total),
+        }
+    }
+}
+
+impl HandValue {
+    pub fn total(self) -> u8 {
+        match self {
+            HandValue::Hard(total) | HandValue::Soft(total) => total,
+        }
+    }
+
+    pub fn is_soft(self) -> bool {
+        match self {
+            HandValue::Hard(_) => false,
+            HandValue::Soft(_) => true,
+        }
+    }
+
+    pub fn is_bust(self) -> bool {
+        self.total() > 21
+    }
+}
+
+/// Whether a `Hand` can still take decisions.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HandState {
+    Playing,
+    /// The player stood or doubled, so the hand takes no more cards.
+    Stood,
+    /// The player gave up the hand, getting back half of the bet, rounded up.
+    Surrendered,
+}
+
+/// The money at stake on a `Hand`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Wager {
+    /// The bet placed before the cards were dealt, or the bet of the hand it was split from.
+    pub base: u32,
+    pub doubled: bool,
+    /// Side bet against a dealer's blackjack, paid 2:1.
+    pub insurance: u32,
+    /// Whether a blackjack was paid 1:1 before the dealer checked the hole card.
+    pub even_money: bool,
+}
+
+impl Wager {
+    /// The main bet, including what was added by doubling.
+    pub fn amount(&self) -> u32 {
+        if self.doubled {
+            self.base * 2
+        } else {
+            self.base
+        }
+    }
+
+    /// Every money at risk, adding up the main bet and the insurance.
+    pub fn total(&self) -> u32 {
+        self.amount() + self.insurance
+    }
+}
+
+/// Most cards a hand can hold, as twenty aces and any other card are over 21.
+pub const MAX_CARDS: usize = 21;
+
+/// The cards of a player or the dealer, keeping their totals up to date as cards are added.
 #[derive(Clone)]
 pub struct Hand {
-    pub cards: Vec<Card>,
-    pub points: u8,
-    aces: u8,
+    cards: [Card; MAX_CARDS],
+    n_cards: u8,
+    /// The total counting every ace as 1 point.
+    hard_total: u8,
+    has_ace: bool,
+    pub wager: Wager,
+    /// Whether the hand comes from splitting a pair, so it cannot be a blackjack.
+    pub split: bool,
+    pub state: HandState,
 }
 
 impl fmt::Display for Hand {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let points: u8 = self.points;
-        let cards_as_string: Vec<String> = self.cards.iter().map(|card| card.to_string()).collect();
+        let cards_as_string: Vec<String> =
+            self.cards().iter().map(|card| card.to_string()).collect();
         let (last, elements): (&String, &[String]) =
             cards_as_string.split_last().expect("Hand was empty.");
 
+        if !elements.is_empty() {
+            write!(f, "{} and ", elements.join(", "))?;
+        }
         write!(
             f,
-            "{} and {} ({} points)",
-            elements.join(", "),
+            "{} ({} points{})",
             last,
-            if points > 0 {
-                points.to_string()
-            } else {
-                String::from("> 21")
-            }
+            self.value(),
+            if self.is_bust() { ", bust" } else { "" }
         )
     }
 }
 
+impl fmt::Debug for Hand {
+    /// Shows only the cards in the hand, not the unused slots of the array.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hand")
+            .field("cards", &self.cards())
+            .field("value", &self.value())
+            .field("wager", &self.wager)
+            .field("split", &self.split)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
 impl Hand {
     /// Creates a new instance of a `Hand` given a `Deck` to get the initial cards.
     pub fn new(deck: &mut Deck) -> Hand {
-        let cards: Vec<Card> = deck.get_initial_cards();
-        let points: u8 = Hand::calculate_points(&cards);
-
-        let mut hand = Hand {
-            cards,
-            points,
-            aces: 0,
-        };
-        for card in hand.cards.clone() {
-            hand.check_if_ace(&card);
-        }
-        hand.check_ace_points();
-        hand
+        Hand::from_cards(&deck.get_initial_cards())
     }
     /// Creates a new instance of a `Hand` given a slice of `Card`s
     pub fn from_cards(cards: &[Card]) -> Hand {
-        let points: u8 = Hand::calculate_points(cards);
-
         let mut hand = Hand {
-            cards: cards.into(),
-            points,
-            aces: 0,
+            cards: [Card::new(Rank::Ace, Suit::Spades); MAX_CARDS],
+            n_cards: 0,
+            hard_total: 0,
+            has_ace: false,
+            wager: Wager::default(),
+            split: false,
+            state: HandState::Playing,
         };
-        for card in hand.cards.clone() {
-            hand.check_if_ace(&card);
+        for &card in cards {
+            hand.add_card(card);
         }
-        hand.check_ace_points();
         hand
     }
 
+    pub fn cards(&self) -> &[Card] {
+        &self.cards[..usize::from(self.n_cards)]
+    }
+
+    /// The total counting every ace as 1 point.
+    pub fn hard_total(&self) -> u8 {
+        self.hard_total
+    }
+
+    /// The total counting one ace as 11 points, if the hand has any.
+    /// It can be greater than 21 even if the hand is not bust.
+    pub fn soft_total(&self) -> u8 {
+        if self.has_ace {
+            self.hard_total + 10
+        } else {
+            self.hard_total
+        }
+    }
+
+    /// The best total of the hand, counting an ace as 11 points only if it does not bust.
+    pub fn value(&self) -> HandValue {
+        let soft_total: u8 = self.soft_total();
+        if soft_total <= 21 && soft_total != self.hard_total() {
+            HandValue::Soft(soft_total)
+        } else {
+            HandValue::Hard(self.hard_total())
+        }
+    }
+
+    /// Shorthand for the total of `value`.
+    pub fn points(&self) -> u8 {
+        self.value().total()
+    }
+
+    /// Whether an ace is being counted as 11 points.
+    pub fn is_soft(&self) -> bool {
+        self.value().is_soft()
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.hard_total > 21
+    }
+
+    /// Whether the hand is made of two cards of the same rank.
+    pub fn is_pair(&self) -> bool {
+        self.n_cards == 2 && self.cards[0].rank() == self.cards[1].rank()
+    }
+
+    /// Whether the hand is made of two cards of the same value, like a king and a queen.
+    pub fn is_pair_of_value(&self) -> bool {
+        self.n_cards == 2 && self.cards[0].value() == self.cards[1].value()
+    }
+
+    /// Whether the hand is an ace and a ten-value card as dealt.
+    /// A 21 made of two cards after splitting is not a blackjack.
     pub fn has_blackjack(&self) -> bool {
-        self.cards.len() == 2 && self.points == 21
+        !self.split && self.n_cards == 2 && self.points() == 21
+    }
+
+    pub fn is_surrendered(&self) -> bool {
+        self.state == HandState::Surrendered
+    }
+
+    /// Whether the hand comes from splitting a pair of aces.
+    pub fn is_split_aces(&self) -> bool {
+        self.split && self.cards[0].rank() == Rank::Ace
     }
 
     /// Resets all the attributes of the instance.
@@ -74,36 +222,84 @@ impl Hand {
 
     /// Deal a new `Card`, taken from the `Deck` given as a parameter.
     pub fn deal_card(&mut self, deck: &mut Deck) {
-        let card: Card = deck.deal_card();
-        self.check_if_ace(&card);
-
-        self.cards.push(card);
-        self.update_points();
-
-        if self.points > 21 {
-            self.points = 0;
-        }
+        self.add_card(deck.deal_card());
     }
 
-    fn check_if_ace(&mut self, card: &Card) {
-        if card.name == "ACE" {
-            self.aces += 1;
+    /// Adds the `card` to the hand, updating its totals.
+    pub fn add_card(&mut self, card: Card) {
+        assert!(
+            usize::from(self.n_cards) < MAX_CARDS,
+            "A hand cannot hold more than {} cards",
+            MAX_CARDS
+        );
+        self.cards[usize::from(self.n_cards)] = card;
+        self.n_cards += 1;
+        if card.rank() == Rank::Ace {
+            self.hard_total += 1;
+            self.has_ace = true;
+        } else {
+            self.hard_total += card.value();
         }
     }
 
-    fn check_ace_points(&mut self) {
-        while self.aces > 0 && self.points > 21 {
-            self.points -= 10;
-            self.aces -= 1;
+    /// Takes the last card out of the hand, updating its totals.
+    pub fn remove_card(&mut self) -> Option<Card> {
+        if self.n_cards == 0 {
+            return None;
         }
+        self.n_cards -= 1;
+        let card: Card = self.cards[usize::from(self.n_cards)];
+        if card.rank() == Rank::Ace {
+            self.hard_total -= 1;
+            self.has_ace = self.cards().iter().any(|card| card.rank() == Rank::Ace);
+        } else {
+            self.hard_total -= card.value();
+        }
+        Some(card)
     }
+}
 
-    fn update_points(&mut self) {
-        self.points = Hand::calculate_points(&self.cards);
-        self.check_ace_points();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_shows_only_the_cards_in_the_hand() {
+        let mut hand = Hand::from_cards(&[
+            Card::new(Rank::Two, Suit::Hearts),
+            Card::new(Rank::Three, Suit::Hearts),
+            Card::new(Rank::Nine, Suit::Clubs),
+        ]);
+        hand.remove_card();
+        let debug: String = format!("{:?}", hand);
+
+        assert!(debug.starts_with("Hand { cards: [Card(TWO of ♥), Card(THREE of ♥)], "));
+        assert!(!debug.contains("ACE") && !debug.contains("NINE"));
     }
 
-    pub fn calculate_points(cards: &[Card]) -> u8 {
-        cards.iter().fold(0, |acc, card| acc + card.name_to_value())
+    #[test]
+    fn totals_follow_added_and_removed_cards() {
+        let mut hand = Hand::from_cards(&[
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::Six, Suit::Spades),
+        ]);
+        assert_eq!(hand.value(), HandValue::Soft(17));
+
+        hand.add_card(Card::new(Rank::Ace, Suit::Clubs));
+        assert_eq!(hand.value(), HandValue::Soft(18));
+        hand.add_card(Card::new(Rank::Nine, Suit::Clubs));
+        assert_eq!(hand.value(), HandValue::Hard(17));
+        hand.add_card(Card::new(Rank::King, Suit::Clubs));
+        assert!(hand.is_bust());
+
+        hand.remove_card();
+        hand.remove_card();
+        hand.remove_card();
+        assert_eq!(hand.value(), HandValue::Soft(17));
+        hand.remove_card();
+        assert_eq!(hand.value(), HandValue::Soft(11));
+        assert_eq!(hand.remove_card(), Some(Card::new(Rank::Ace, Suit::Hearts)));
+        assert_eq!(hand.value(), HandValue::Hard(0));
+        assert_eq!(hand.remove_card(), None);
     }
 }
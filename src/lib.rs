@@ -0,0 +1,17 @@
+//! The 21 BlackJack game engine.
+//!
+//! A `game::Game` runs the rounds of a table: the players bet, the cards are dealt,
+//! every hand plays its actions, the dealer plays and the hands are settled.
+//! It reports what happened as `game::Event`s, so it can be driven by any front-end.
+
+pub mod analysis;
+pub mod args;
+pub mod chart;
+pub mod deck;
+pub mod game;
+pub mod hand;
+pub mod player;
+pub mod rules;
+pub mod simulation;
+pub mod strategy;
+pub mod training;
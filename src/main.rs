@@ -1,55 +1,146 @@
-mod deck;
-mod hand;
-mod player;
-
+use std::env;
 use std::io;
 use std::io::Write;
-
-use deck::Deck;
-use hand::Hand;
-use player::Player;
+use std::process;
+
+use blackjack_rust::analysis::{self, ExpectedValues, Shoe};
+use blackjack_rust::args;
+use blackjack_rust::chart::Chart;
+use blackjack_rust::deck::{Card, Deck, Rank};
+use blackjack_rust::game::{Action, Event, Game, Outcome, Phase};
+use blackjack_rust::hand::Hand;
+use blackjack_rust::player::{ActionError, Player};
+use blackjack_rust::rules::{SplitPairs, TableRules};
+use blackjack_rust::training::{HandCategory, TrainingStats};
 
 fn main() {
+    let args: Vec<String> = env::args().collect();
+    if let Err(error) = run(&args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut rules: TableRules = TableRules::from_args(args)?;
+    if args.get(1).map(String::as_str) == Some("dealer-table") {
+        print_dealer_table(&rules);
+        return Ok(());
+    }
+    let seed: Option<u64> = args::option(args, "--seed")?;
+    let penetration: Option<f64> = args::option(args, "--penetration")?;
+    let chart_path: Option<String> = args::option(args, "--chart")?;
     println!("Welcome to BlackJack!\n");
 
-    let mut players: Vec<Player> = Vec::new();
-    let n_of_decks: u8 = loop {
-        match ask_user("How many decks do you wanna use? (4-8)")
-            .trim()
-            .parse()
-        {
-            Ok(val) => {
-                if val >= 4 && val <= 8 {
-                    break val;
-                } else {
-                    println!("The number of decks must be between 4 and 8");
+    if !args::flag(args, "--decks") {
+        rules.n_decks = loop {
+            match ask_user("How many decks do you wanna use? (4-8)")
+                .trim()
+                .parse()
+            {
+                Ok(val) => {
+                    if (4..=8).contains(&val) {
+                        break val;
+                    } else {
+                        println!("The number of decks must be between 4 and 8");
+                    }
+                }
+                Err(_) => {
+                    println!("Expected integer input");
                 }
             }
-            Err(_) => {
-                println!("Expected integer input");
-            }
+        };
+    }
+    let mut deck = match seed {
+        Some(seed) => Deck::from_seed(rules.n_decks, seed),
+        None => Deck::new(rules.n_decks),
+    };
+    if let Some(penetration) = penetration {
+        deck.set_penetration(penetration);
+    }
+    println!(
+        "####### Game Started ({}) #######",
+        if rules.dealer_hits_soft_17 {
+            "H17"
+        } else {
+            "S17"
         }
+    );
+    println!(
+        "Seed: {seed} (run with `--seed {seed}` to replay this session)",
+        seed = deck.seed()
+    );
+    println!("Rules: {}", rules);
+    let chart: Chart = match chart_path {
+        Some(path) => Chart::load(&path, &rules)
+            .map_err(|error| format!("Invalid strategy chart {}: {}", path, error))?,
+        None => Chart::basic(&rules),
+    };
+    let mut training: Option<TrainingStats> = if args::flag(args, "--training") {
+        println!("Training mode: every decision is checked against the strategy chart");
+        Some(TrainingStats::new())
+    } else {
+        None
     };
-    let mut deck = Deck::new(n_of_decks);
-    let mut dealer_hand = Hand::new(&mut deck);
-    println!("####### Game Started #######");
-    start_game(&mut players, &mut deck);
+    let mut game = Game::new(rules, deck);
+    start_game(&mut game);
     loop {
-        println!(
-            "\nThe first card of the dealer is {}\n",
-            dealer_hand.cards[0]
-        );
+        ask_bets(&mut game);
+        game.deal().expect("Every player has bet");
+        print_events(&mut game);
 
-        for player in players.iter_mut() {
-            player_turn(player, &mut deck);
+        if game.phase() == Phase::Surrender {
+            ask_surrender(&mut game);
+        }
+        if game.phase() == Phase::Insurance {
+            ask_insurance(&mut game);
         }
 
-        dealer_turn(&mut dealer_hand, &mut deck);
-        end_game(&mut players, &dealer_hand);
-        if !next_game(&mut players, &mut dealer_hand, &mut deck) {
+        player_turns(&mut game, &chart, training.as_mut());
+
+        if !next_game(&mut game) {
             break;
         }
     }
+    if let Some(stats) = training {
+        print_training_summary(&stats);
+    }
+    Ok(())
+}
+
+/// Prints the probabilities of the dealer's final hand for every upcard,
+/// for the number of decks and the soft 17 rule of the `rules`.
+fn print_dealer_table(rules: &TableRules) {
+    println!(
+        "Dealer final hand, {} decks, {}\n",
+        rules.n_decks,
+        if rules.dealer_hits_soft_17 {
+            "H17"
+        } else {
+            "S17"
+        }
+    );
+    println!(
+        "{:>6}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+        "Upcard", "17", "18", "19", "20", "21", "BJ", "Bust"
+    );
+    for (upcard, outcomes) in
+        analysis::dealer_table(&Shoe::new(rules.n_decks), rules.dealer_hits_soft_17)
+    {
+        let upcard: String = match upcard {
+            Rank::Ace => String::from("A"),
+            rank => rank.value().to_string(),
+        };
+        let mut row: String = format!("{:>6}", upcard);
+        for probability in outcomes
+            .totals
+            .iter()
+            .chain(&[outcomes.blackjack, outcomes.bust])
+        {
+            row.push_str(&format!("{:>7.2}%", probability * 100.0));
+        }
+        println!("{}", row);
+    }
 }
 
 fn ask_user(prompt: &str) -> String {
@@ -75,9 +166,9 @@ fn ask_user_number(prompt: &str) -> Option<u32> {
     }
 }
 
-fn start_game(players: &mut Vec<Player>, deck: &mut Deck) {
+fn start_game(game: &mut Game) {
     let number_of_people: u8 = ask_number_of_people();
-    ask_and_set_player_attributes(number_of_people, players, deck);
+    ask_and_set_player_attributes(number_of_people, game);
 }
 
 fn ask_number_of_people() -> u8 {
@@ -96,7 +187,7 @@ fn ask_number_of_people() -> u8 {
     }
 }
 
-fn ask_and_set_player_attributes(number_of_people: u8, players: &mut Vec<Player>, deck: &mut Deck) {
+fn ask_and_set_player_attributes(number_of_people: u8, game: &mut Game) {
     for i in 0..number_of_people {
         let name: String = ask_user(format!("\nPlease, enter your name player {}", i + 1).as_str());
         loop {
@@ -109,204 +200,360 @@ fn ask_and_set_player_attributes(number_of_people: u8, players: &mut Vec<Player>
             if initial_money < 50 {
                 println!("The initial money must be greater or equal than 50\n");
             } else {
-                players.push(Player::new(String::from(name.trim()), initial_money, deck));
+                game.add_player(String::from(name.trim()), initial_money);
                 break;
             }
         }
     }
 }
 
-fn ask_player_bet(player: &Player) -> u32 {
-    loop {
-        let bet: u32 =
-            match ask_user_number("What bet do you wanna make? (Use only integral values)") {
-                Some(value) => value,
-                None => continue,
-            };
+fn ask_bets(game: &mut Game) {
+    for i in 0..game.players().len() {
+        let player: &Player = &game.players()[i];
+        println!(
+            "\n{player}, your actual money is {actual_money} €\n",
+            player = player,
+            actual_money = player.actual_money
+        );
+        loop {
+            let bet: u32 =
+                match ask_user_number("What bet do you wanna make? (Use only integral values)") {
+                    Some(value) => value,
+                    None => continue,
+                };
 
-        if bet > player.actual_money {
-            println!("Your bet cannot be greater than your actual money.\n");
-        } else {
-            break bet;
+            match game.place_bet(i, bet) {
+                Ok(()) => break,
+                Err(error) => println!("{}\n", action_error_message(error)),
+            }
         }
     }
 }
 
-fn hand_win_or_lose(hand: &Hand) -> bool {
-    if hand.has_blackjack() {
-        println!("BLACKJACK!\n");
-        true
-    } else {
-        match hand.points {
-            21 => {
-                println!("YOU GOT 21 POINTS!\n");
-                true
+fn ask_surrender(game: &mut Game) {
+    for i in 0..game.players().len() {
+        let player: &Player = &game.players()[i];
+        if player.hands[0].has_blackjack() {
+            continue;
+        }
+        let decision: String = ask_user(
+            format!(
+                "\n{}, your cards are {}\nThe dealer may have a blackjack. Do you want to surrender? (y/n)",
+                player, player.hands[0]
+            )
+            .as_str(),
+        );
+        if check_if_yes(decision.as_str()) {
+            if let Err(error) = game.surrender(i) {
+                println!("{}", action_error_message(error));
+            }
+        }
+    }
+    game.close_surrender().expect("Early surrender was offered");
+    print_events(game);
+}
+
+fn ask_insurance(game: &mut Game) {
+    for i in 0..game.players().len() {
+        let player: &Player = &game.players()[i];
+        let max_insurance: u32 = player.hands[0].wager.base / 2;
+
+        if player.hands[0].is_surrendered() {
+            continue;
+        } else if player.hands[0].has_blackjack() {
+            let decision: String = ask_user(
+                format!(
+                    "\n{}, you have a blackjack! Do you want even money? (y/n)",
+                    player
+                )
+                .as_str(),
+            );
+            if check_if_yes(decision.as_str()) {
+                if let Err(error) = game.take_even_money(i) {
+                    println!("{}", action_error_message(error));
+                }
             }
-            0 => {
-                println!("BUST.\nI'm afraid you lose this game :(\n");
-                true
+        } else if max_insurance > 0 {
+            let decision: String = ask_user(
+                format!(
+                    "\n{}, your cards are {}\nDo you want insurance? (y/n)",
+                    player, player.hands[0]
+                )
+                .as_str(),
+            );
+            if check_if_yes(decision.as_str()) {
+                loop {
+                    let money: u32 = match ask_user_number(
+                        format!(
+                            "How much do you want to insure? (up to {} €)",
+                            max_insurance
+                        )
+                        .as_str(),
+                    ) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+
+                    match game.insure(i, money) {
+                        Ok(()) => break,
+                        Err(error) => println!("{}\n", action_error_message(error)),
+                    }
+                }
             }
-            _ => false,
         }
     }
+    game.close_insurance().expect("Insurance was offered");
+    print_events(game);
 }
 
 fn check_if_yes(user_decision: &str) -> bool {
     ["y", "yes", "1", "true"].contains(&user_decision.to_lowercase().trim())
 }
 
-fn player_turn(player: &mut Player, deck: &mut Deck) {
-    println!(
-        "\n{player}, your actual money is {actual_money} €\n",
-        player = player,
-        actual_money = player.actual_money
-    );
-    let bet: u32 = ask_player_bet(player);
-    player.bet(bet);
-    let player_first_hand: &Hand = &player.hands[0];
-    println!(
-        "\nYour cards are:\n{} and {} ({} points)\n",
-        player_first_hand.cards[0], player_first_hand.cards[1], player_first_hand.points
-    );
-    for i in 0..2 {
-        let mut has_doubled = false;
-        while !hand_win_or_lose(&player.hands[i])
-            // If the player has doubled, he can only ask for one more card
-            && (!has_doubled || player.hands[i].cards.len() < 3)
+/// Plays the turns of every hand. In training mode, every action is checked against
+/// the `chart` and recorded in `training`.
+fn player_turns(game: &mut Game, chart: &Chart, mut training: Option<&mut TrainingStats>) {
+    while let Some((player, hand)) = game.turn() {
+        let hands: &[Hand] = &game.players()[player].hands;
+        if hands.len() > 1 {
+            println!("\n(Hand #{}) {}", hand + 1, hands[hand]);
+        }
+        let actions: Vec<Action> = game.available_actions(player, hand);
+        let mut commands: Vec<&str> = actions
+            .iter()
+            .map(|&action| action_command(action))
+            .collect();
+        commands.push("(?) hint");
+        let commands: String = commands.join(", ");
+        let upcard: Card = game.dealer_hand().cards()[0];
+        let recommended: Action = chart.recommend(&hands[hand], upcard, &actions);
+        let category: HandCategory = HandCategory::of(&hands[hand], &actions);
+        let cards: String = hands[hand].to_string();
+        let action: Action = match ask_user(
+            format!("What do you want to do?\nAvailable Commands: {}", commands).as_str(),
+        )
+        .to_lowercase()
+        .trim()
         {
-            if player.hands.len() > 1 {
-                println!("\n(Hand #{})", i + 1);
+            "h" | "hit" => Action::Hit,
+            "s" | "stand" => Action::Stand,
+            "sp" | "split" => Action::Split,
+            "d" | "double" => Action::Double,
+            "surr" | "surrender" => Action::Surrender,
+            "?" | "hint" => {
+                println!("Strategy says: {}", recommended);
+                print_expected_values(game, &hands[hand], &actions);
+                continue;
             }
-            match ask_user("What do you want to do?\nAvailable Commands: (h)it, (s)tand, (sp)lit, (d)ouble, (surr)ender")
-                .to_lowercase()
-                .trim()
-            {
-                "h" | "hit" => {
-                    player.hit(deck, i);
-                    println!(
-                        "Now, the cards are: {}",
-                        player.hands[i]
-                    );
-                }
-                "s" | "stand" => {
-                    println!("{} stood", player);
-                    break;
-                }
-                "sp" | "split" => {
-                    if !has_doubled {
-                        match player.split(deck) {
-                            Some(error_message) => println!("{}", error_message),
-                            None => {
-                                println!("You have splitted the hand!\n")
-                            }
-                        }
-                    } else {
-                        println!("Cannot split because you have already doubled\n");
-                    }
-                }
-                "d" | "double" => {
-                    if !has_doubled {
-                        match player.double() {
-                            Some(error_message) => println!("{}", error_message),
-                            None => {
-                                has_doubled = true;
-                                println!("You have doubled your hand!\n")
-                            }
-                        }
-                    } else {
-                        println!("Cannot double more than once!\n");
-                    }
-                }
-                "surr" | "surrender" => {
-                    if !has_doubled {
-                        match player.surrender() {
-                            Some(error_message) => println!("{}", error_message),
-                            None => println!("You have surrendered!\n")
-                        }
-                    } else {
-                        println!("Cannot surrender because you have already doubled\n");
-                    }
-                }
-
-                _ => println!("Invalid command!\nAvailable Commands: (h)it, (s)tand, (sp)lit, (d)ouble, (surr)ender"),
+            _ => {
+                println!("Invalid command!\nAvailable Commands: {}", commands);
+                continue;
+            }
+        };
+        if let Err(error) = game.act(action) {
+            println!("{}", action_error_message(error));
+        } else if let Some(stats) = training.as_mut() {
+            if stats.record(category, action, recommended) {
+                println!(
+                    "Mistake! With {} against {} strategy says: {}",
+                    cards, upcard, recommended
+                );
             }
         }
-        if player.hands.len() == 1 {
-            break;
+        print_events(game);
+    }
+}
+
+/// Shows the expected value of the available `actions` of the `hand`,
+/// drawing from the cards left in the shoe since the last shuffle.
+fn print_expected_values(game: &Game, hand: &Hand, actions: &[Action]) {
+    let dealer_cards: &[Card] = game.dealer_hand().cards();
+    let upcard: Card = dealer_cards[0];
+    // The cards on the table are no longer in the shoe, but the players
+    // cannot see the hole card, so it could be any of the cards left
+    let mut cards: Vec<Card> = game.deck().undealt_cards().to_vec();
+    cards.extend_from_slice(&dealer_cards[1..]);
+    let shoe: Shoe = Shoe::from_cards(&cards);
+    let values: ExpectedValues =
+        analysis::expected_values(hand, upcard, &shoe, game.rules(), actions);
+
+    let mut line: String = format!("Expected values: stand {:+.3}", values.stand);
+    for &(action, value) in [
+        (Action::Hit, values.hit),
+        (Action::Double, values.double),
+        (Action::Split, values.split),
+        (Action::Surrender, values.surrender),
+    ]
+    .iter()
+    {
+        if let Some(value) = value {
+            line.push_str(&format!(", {} {:+.3}", action, value));
         }
     }
+    println!("{}\n", line);
 }
 
-fn dealer_lost(dealer_hand: &Hand) -> bool {
-    if dealer_hand.points == 0 {
-        println!("The dealer busted. The game ended :)\n");
-        true
-    } else {
-        false
+fn print_training_summary(stats: &TrainingStats) {
+    println!("\n####### Training Summary #######");
+    for &category in HandCategory::ALL.iter() {
+        match stats.error_rate(category) {
+            Some(error_rate) => println!(
+                "{}: {} mistakes in {} decisions ({:.1}% error rate)",
+                category,
+                stats.mistakes(category),
+                stats.decisions(category),
+                error_rate * 100.0
+            ),
+            None => println!("{}: no decisions", category),
+        }
     }
 }
 
-fn dealer_turn(dealer_hand: &mut Hand, deck: &mut Deck) {
-    println!(
-        "\nThe dealer's cards are {} and {}\n",
-        dealer_hand.cards[0], dealer_hand.cards[1]
-    );
-    while !dealer_lost(dealer_hand) && dealer_hand.points < 17 {
-        println!("The dealer is going to hit a card\n");
-        dealer_hand.deal_card(deck);
-        println!("Now, the cards of the dealer are: {}", dealer_hand);
+fn action_command(action: Action) -> &'static str {
+    match action {
+        Action::Hit => "(h)it",
+        Action::Stand => "(s)tand",
+        Action::Split => "(sp)lit",
+        Action::Double => "(d)ouble",
+        Action::Surrender => "(surr)ender",
     }
 }
 
-fn end_game(players: &mut [Player], dealer_hand: &Hand) {
-    println!("####### Game Finished #######\n");
-    let dealer_points = dealer_hand.points;
+fn action_error_message(error: ActionError) -> &'static str {
+    match error {
+        ActionError::InsufficientFunds => "You have not enough money!",
+        ActionError::NotOffered => "You cannot do that right now!",
+        ActionError::AlreadyHit => "Cannot do that because you have already hit!",
+        ActionError::AlreadyDoubled => "Cannot double more than once!",
+        ActionError::AlreadySplit => "Cannot do that after splitting at this table!",
+        ActionError::AlreadySurrendered => "You have already surrendered!",
+        ActionError::NotAPair(SplitPairs::SameRank) => {
+            "Cannot split because your cards are not of the same rank!"
+        }
+        ActionError::NotAPair(SplitPairs::SameValue) => {
+            "Cannot split because your cards are not of the same value!"
+        }
+        ActionError::TooManyHands => {
+            "Cannot split because you have reached the maximum number of hands!"
+        }
+        ActionError::ResplitAces => "Cannot split aces again at this table!",
+        ActionError::SplitAces => "Cannot take more cards on split aces at this table!",
+        ActionError::DoubleNotAllowed => "Cannot double on this total at this table!",
+        ActionError::SurrenderNotAllowed => "Cannot surrender at this table!",
+        ActionError::Blackjack => "Cannot surrender nor insure a blackjack!",
+        ActionError::NoBlackjack => "Cannot take even money because you have not got a blackjack!",
+        ActionError::InsuranceTooHigh => "Cannot insure more than half of your bet!",
+        ActionError::AlreadyInsured => "Cannot take even money because you are insured!",
+        ActionError::EvenMoneyTaken => "Cannot insure because you took even money!",
+        ActionError::BetOutOfLimits => "Your bet must be between the table minimum and maximum.",
+        ActionError::NoPlayers => "There are no players at the table!",
+        ActionError::MissingBet => "Every player must bet before dealing!",
+    }
+}
 
-    for player in players.iter_mut() {
-        for (i, hand) in player.hands.clone().iter().enumerate() {
-            let hand_points: u8 = hand.points;
-            if hand_points > dealer_points
-                || player.hands[0].has_blackjack() && !dealer_hand.has_blackjack()
-            {
-                let money_earned: u32 = player.win(i);
-                println!(
-                    "{player}{} won {money}€! :)\n",
-                    // If it hasn't splitted, don't show the hand's index
-                    if player.hands.len() == 1 {
-                        String::new()
-                    } else {
-                        format!(" (#{} hand)", i + 1)
-                    },
-                    player = player,
-                    money = money_earned,
-                );
-            } else if hand_points == 0 || hand_points < dealer_points {
-                println!(
-                    "{player}{} lost! :(\n",
-                    if player.hands.len() == 1 {
-                        String::new()
-                    } else {
-                        format!(" (#{} hand)", i + 1)
-                    },
-                    player = player,
-                );
-                player.lose();
-            } else {
-                println!(
-                    "{player}{} tied! :|\n",
-                    if player.hands.len() == 1 {
-                        String::new()
-                    } else {
-                        format!(" (#{} hand)", i + 1)
-                    },
-                    player = player,
-                );
+/// Shows the player's hand index if the player has splitted.
+fn hand_label(player: &Player, hand: usize) -> String {
+    if player.hands.len() == 1 {
+        String::new()
+    } else {
+        format!(" (#{} hand)", hand + 1)
+    }
+}
+
+fn print_events(game: &mut Game) {
+    for event in game.take_events() {
+        match event {
+            Event::Shuffled {
+                discarded,
+                remaining,
+            } => println!(
+                "The cut card has been reached, shuffling the {} cards of the discard tray \
+                 back into the shoe ({} cards left)...\n",
+                discarded, remaining
+            ),
+            Event::ShoeRanOut { discarded } => println!(
+                "The shoe has run out, shuffling the {} cards of the discard tray back in...\n",
+                discarded
+            ),
+            Event::DealerUpcard(card) => {
+                println!("\nThe first card of the dealer is {}\n", card)
+            }
+            Event::SurrenderOffered => println!("The dealer offers early surrender."),
+            Event::InsuranceOffered => println!("The dealer offers insurance."),
+            Event::Insured { player, money } => {
+                println!("{} insured {} €\n", game.players()[player], money)
+            }
+            Event::EvenMoney { player } => {
+                println!("{} took even money\n", game.players()[player])
+            }
+            Event::DealerPeeked { blackjack } => {
+                if blackjack {
+                    println!("The dealer has a blackjack!\n")
+                } else {
+                    println!("The dealer peeked, there is no blackjack.\n")
+                }
+            }
+            Event::TurnStarted { player, cards } => {
+                println!("\n{}, your cards are:\n{}\n", game.players()[player], cards)
+            }
+            Event::Hit { cards, .. } => println!("Now, the cards are: {}", cards),
+            Event::Stood { player, .. } => println!("{} stood", game.players()[player]),
+            Event::Doubled { cards, .. } => {
+                println!("You have doubled your hand!\n");
+                println!("Now, the cards are: {}", cards);
+            }
+            Event::Split { first, second, .. } => {
+                println!("You have splitted the hand!\n");
+                println!("The new hands are: {}\nand: {}\n", first, second);
+            }
+            Event::Surrendered { .. } => println!("You have surrendered!\n"),
+            Event::Blackjack { .. } => println!("BLACKJACK!\n"),
+            Event::TwentyOne { .. } => println!("YOU GOT 21 POINTS!\n"),
+            Event::Bust { .. } => println!("BUST.\nI'm afraid you lose this game :(\n"),
+            Event::DealerRevealed(cards) => println!("\nThe dealer's cards are {}\n", cards),
+            Event::DealerHit(cards) => {
+                println!("The dealer is going to hit a card\n");
+                println!("Now, the cards of the dealer are: {}", cards);
+            }
+            Event::DealerBust => println!("The dealer busted. The game ended :)\n"),
+            Event::RoundFinished => println!("####### Game Finished #######\n"),
+            Event::InsuranceSettled {
+                player, outcome, ..
+            } => match outcome {
+                Outcome::Won(money) => println!(
+                    "{}'s insurance won {}€! :)\n",
+                    game.players()[player],
+                    money
+                ),
+                _ => println!("{}'s insurance lost! :(\n", game.players()[player]),
+            },
+            Event::Settled {
+                player,
+                hand,
+                outcome,
+            } => {
+                let player: &Player = &game.players()[player];
+                match outcome {
+                    Outcome::Won(money) => println!(
+                        "{}{} won {}€! :)\n",
+                        player,
+                        hand_label(player, hand),
+                        money
+                    ),
+                    Outcome::Lost(money) => println!(
+                        "{}{} lost {}€! :(\n",
+                        player,
+                        hand_label(player, hand),
+                        money
+                    ),
+                    Outcome::Tied => println!("{}{} tied! :|\n", player, hand_label(player, hand)),
+                }
             }
         }
     }
 }
 
-fn ask_if_next_game(player: &Player) -> bool {
+fn ask_if_next_game(player: &Player, min_bet: u32) -> bool {
     let player_next_game: bool;
 
     let final_balance: String = format!(
@@ -314,7 +561,7 @@ fn ask_if_next_game(player: &Player) -> bool {
         i64::from(player.actual_money) - i64::from(player.initial_money)
     );
 
-    if player.actual_money > 0 {
+    if player.actual_money >= min_bet {
         let decision: String =
             ask_user(format!("\n{}, do you want to play again? (y/n)\n", player).as_str());
 
@@ -330,26 +577,21 @@ fn ask_if_next_game(player: &Player) -> bool {
     } else {
         player_next_game = false;
         println!(
-            "{}, you have lost all your money. Thanks for playing\n",
-            player
+            "{}, you have not enough money to keep playing. Thanks for playing, \
+             your final balance is {}\n",
+            player, final_balance
         );
     }
     player_next_game
 }
 
-fn next_game(players: &mut Vec<Player>, dealer_hand: &mut Hand, deck: &mut Deck) -> bool {
-    players.retain(ask_if_next_game);
-
-    for player in players.iter_mut() {
-        player.reset_hands(deck);
-    }
+fn next_game(game: &mut Game) -> bool {
+    // Players need money for a bet of at least 1 to be dealt in
+    let min_bet: u32 = game.rules().min_bet.max(1);
+    game.next_round();
+    game.retain_players(|player| ask_if_next_game(player, min_bet));
 
     println!("\n\n\n");
 
-    if !players.is_empty() {
-        dealer_hand.initialize_attributes(deck);
-        true
-    } else {
-        false
-    }
+    !game.players().is_empty()
 }
@@ -1,14 +1,91 @@
-use crate::deck::{Card, Deck};
-use crate::hand::Hand;
+use crate::deck::{Card, Deck, Rank};
+use crate::hand::{Hand, HandState};
+use crate::rules::{SplitPairs, Surrender, TableRules};
 
+use std::error::Error;
 use std::fmt;
 
-#[derive(Clone)]
+/// Why a player cannot make a decision, or the game cannot move on to the next phase.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ActionError {
+    /// The player cannot cover the extra bet.
+    InsufficientFunds,
+    /// It is nobody's turn, or the decision is not being offered right now.
+    NotOffered,
+    /// The hand has taken more than the two initial cards.
+    AlreadyHit,
+    AlreadyDoubled,
+    /// The hand comes from a split and the rules do not allow the decision after splitting.
+    AlreadySplit,
+    AlreadySurrendered,
+    /// The two cards do not make a pair under the table's rules.
+    NotAPair(SplitPairs),
+    /// The player already has the maximum number of hands.
+    TooManyHands,
+    /// Aces cannot be split again at this table.
+    ResplitAces,
+    /// Split aces cannot take more cards at this table.
+    SplitAces,
+    /// The total of the hand cannot be doubled at this table.
+    DoubleNotAllowed,
+    /// The table does not offer surrender.
+    SurrenderNotAllowed,
+    /// A blackjack cannot be surrendered nor insured, even money is offered instead.
+    Blackjack,
+    /// Even money is only paid on a blackjack.
+    NoBlackjack,
+    /// The insurance is more than half of the bet.
+    InsuranceTooHigh,
+    /// The hand is insured, so even money cannot be taken too.
+    AlreadyInsured,
+    /// Even money was taken, so the hand cannot be insured too.
+    EvenMoneyTaken,
+    /// The bet is below the table minimum or above the table maximum.
+    BetOutOfLimits,
+    /// The cards cannot be dealt because nobody is sitting at the table.
+    NoPlayers,
+    /// The cards cannot be dealt because a player has not bet yet.
+    MissingBet,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            ActionError::InsufficientFunds => "not enough money",
+            ActionError::NotOffered => "the decision is not being offered",
+            ActionError::AlreadyHit => "the hand has already hit",
+            ActionError::AlreadyDoubled => "the hand has already been doubled",
+            ActionError::AlreadySplit => "the hand comes from a split",
+            ActionError::AlreadySurrendered => "the hand has already been surrendered",
+            ActionError::NotAPair(SplitPairs::SameRank) => "the cards are not of the same rank",
+            ActionError::NotAPair(SplitPairs::SameValue) => "the cards are not of the same value",
+            ActionError::TooManyHands => "the maximum number of hands has been reached",
+            ActionError::ResplitAces => "aces cannot be split again",
+            ActionError::SplitAces => "split aces cannot take more cards",
+            ActionError::DoubleNotAllowed => "the total cannot be doubled",
+            ActionError::SurrenderNotAllowed => "surrender is not offered",
+            ActionError::Blackjack => "the hand is a blackjack",
+            ActionError::NoBlackjack => "the hand is not a blackjack",
+            ActionError::InsuranceTooHigh => "the insurance is more than half of the bet",
+            ActionError::AlreadyInsured => "the hand is already insured",
+            ActionError::EvenMoneyTaken => "even money has already been taken",
+            ActionError::BetOutOfLimits => "the bet is outside the table limits",
+            ActionError::NoPlayers => "there are no players at the table",
+            ActionError::MissingBet => "a player has not bet yet",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl Error for ActionError {}
+
+#[derive(Debug, Clone)]
 pub struct Player {
     pub hands: Vec<Hand>,
     pub name: String,
     pub initial_money: u32,
     pub actual_money: u32,
+    /// The bet for the next hand to be dealt.
     pub bet: u32,
 }
 
@@ -20,114 +97,249 @@ impl fmt::Display for Player {
 
 impl Player {
     /// Create a new instance of a `Player`,
-    /// given a `name`, a `initial_money`,
-    /// and a `deck` to create the `hands` field
-    pub fn new(name: String, initial_money: u32, deck: &mut Deck) -> Player {
+    /// given a `name` and a `initial_money`.
+    /// The `hands` are dealt when the round starts
+    pub fn new(name: String, initial_money: u32) -> Player {
         Player {
-            hands: vec![Hand::new(deck)],
+            hands: Vec::new(),
             name,
             initial_money,
             actual_money: initial_money,
             bet: 0,
         }
     }
-    /// Reset the attributes of the `Hand`s of the instance.
-    pub fn reset_hands(&mut self, deck: &mut Deck) {
+    /// Move the cards of every `Hand` of the instance to the `Deck`'s discard tray.
+    pub fn discard_hands(&mut self, deck: &mut Deck) {
         self.hands
-            .iter_mut()
-            .for_each(|hand| hand.initialize_attributes(deck))
+            .drain(..)
+            .for_each(|hand| deck.discard(hand.cards()));
+    }
+
+    /// Deal a new `Hand` with the player's bet, replacing the previous ones.
+    pub fn reset_hands(&mut self, deck: &mut Deck) {
+        let mut hand = Hand::new(deck);
+        hand.wager.base = self.bet;
+        self.hands = vec![hand];
     }
 
     pub fn bet(&mut self, money: u32) {
         self.bet = money;
     }
 
-    pub fn hit(&mut self, deck: &mut Deck, hand_index: usize) {
-        self.hands[hand_index].deal_card(deck)
+    /// The money the player risks in the current round, adding up the wagers of every hand.
+    pub fn money_on_table(&self) -> u32 {
+        self.hands.iter().map(|hand| hand.wager.total()).sum()
     }
 
-    /// Double the player's bet if applicable, return an error message otherwise
-    pub fn double(&mut self) -> Option<&str> {
-        if self.bet * 2 > self.actual_money {
-            Some("Cannot double because you have not enough money!")
-        } else if self.hands[0].cards.len() > 2 {
-            Some("Cannot double because you have already hit!")
+    /// Insure the player's bet if applicable, return the reason why not otherwise
+    pub fn insure(&mut self, money: u32) -> Result<(), ActionError> {
+        if self.hands[0].is_surrendered() {
+            Err(ActionError::AlreadySurrendered)
+        } else if self.hands[0].wager.even_money {
+            Err(ActionError::EvenMoneyTaken)
+        } else if self.hands[0].has_blackjack() {
+            Err(ActionError::Blackjack)
+        } else if money > self.hands[0].wager.base / 2 {
+            Err(ActionError::InsuranceTooHigh)
+        } else if self.money_on_table() - self.hands[0].wager.insurance + money > self.actual_money
+        {
+            Err(ActionError::InsufficientFunds)
         } else {
-            if self.hands.get(1).is_some() {
-                self.bet += self.bet / 2;
-            } else {
-                self.bet *= 2;
-            }
-            None
+            self.hands[0].wager.insurance = money;
+            Ok(())
         }
     }
 
-    /// Perform the corresponding operations to the player's surrender if applicable,
-    /// return an error message otherwise
-    pub fn surrender(&mut self) -> Option<&str> {
-        if self.hands[0].cards.len() != 2 {
-            Some("Cannot surrender because you have already hit!")
-        } else if self.hands.get(1).is_some() {
-            Some("Cannot surrender because you have already splitted!")
+    /// Take even money for the player's blackjack if applicable,
+    /// return the reason why not otherwise
+    pub fn take_even_money(&mut self) -> Result<(), ActionError> {
+        if !self.hands[0].has_blackjack() {
+            Err(ActionError::NoBlackjack)
+        } else if self.hands[0].wager.insurance > 0 {
+            Err(ActionError::AlreadyInsured)
         } else {
-            self.bet /= 2;
-            self.hands[0].points = 0;
-            None
+            self.hands[0].wager.even_money = true;
+            Ok(())
         }
     }
 
-    /// Splits the player's hand if applicable, return an error message otherwise
-    pub fn split(&mut self, deck: &mut Deck) -> Option<&str> {
-        let first_hand_cards: &Vec<Card> = &self.hands[0].cards;
+    /// Deal a new card to the given hand if applicable, return the reason why not otherwise
+    pub fn hit(
+        &mut self,
+        deck: &mut Deck,
+        hand_index: usize,
+        rules: &TableRules,
+    ) -> Result<(), ActionError> {
+        self.can_hit(hand_index, rules)?;
+        self.hands[hand_index].deal_card(deck);
+        Ok(())
+    }
 
-        if self.bet * 2 > self.actual_money {
-            Some("Cannot split because you have not enough money!")
-        } else if self.hands.get(1).is_some() {
-            Some("Cannot split because you have already splitted!")
-        } else if first_hand_cards.len() != 2 {
-            Some("Cannot split because you have already hit!")
-        } else if first_hand_cards[0].name != first_hand_cards[1].name {
-            Some("Cannot split because your cards are not equal!")
+    /// Whether the given hand can take another card
+    pub fn can_hit(&self, hand_index: usize, rules: &TableRules) -> Result<(), ActionError> {
+        if self.hands[hand_index].is_split_aces() && !rules.hit_split_aces {
+            Err(ActionError::SplitAces)
         } else {
-            self.bet *= 2;
-            let cards: Vec<Card> = vec![
-                self.hands[0].cards.pop().expect("Failed to split"),
-                deck.deal_card(),
-            ];
+            Ok(())
+        }
+    }
+
+    /// Double the bet of the given hand if applicable, return the reason why not otherwise
+    pub fn double(&mut self, hand_index: usize, rules: &TableRules) -> Result<(), ActionError> {
+        self.can_double(hand_index, rules)?;
+        self.hands[hand_index].wager.doubled = true;
+        Ok(())
+    }
 
-            self.hands.push(Hand::from_cards(&cards));
+    /// Whether the bet of the given hand can be doubled
+    pub fn can_double(&self, hand_index: usize, rules: &TableRules) -> Result<(), ActionError> {
+        let hand: &Hand = &self.hands[hand_index];
 
-            self.hands[0].deal_card(deck);
-            None
+        if self.money_on_table() + hand.wager.base > self.actual_money {
+            Err(ActionError::InsufficientFunds)
+        } else if hand.split && !rules.double_after_split {
+            Err(ActionError::AlreadySplit)
+        } else if hand.wager.doubled {
+            Err(ActionError::AlreadyDoubled)
+        } else if hand.cards().len() > 2 {
+            Err(ActionError::AlreadyHit)
+        } else if !rules.double_on.allows(hand.value()) {
+            Err(ActionError::DoubleNotAllowed)
+        } else if hand.is_split_aces() && !rules.hit_split_aces {
+            Err(ActionError::SplitAces)
+        } else {
+            Ok(())
         }
     }
 
-    /// Perform the corresponding operations with the player's money,
-    /// return the amount of money the player wins
-    pub fn win(&mut self, hand_index: usize) -> u32 {
-        let money_before = self.actual_money;
-        if self.hands.len() == 1 {
-            self.actual_money += self.bet;
-            if self.hands[hand_index].has_blackjack() {
-                self.actual_money += self.bet / 2;
-            }
+    /// Give up the player's hand, keeping half of the bet, if applicable,
+    /// return the reason why not otherwise
+    pub fn surrender(&mut self, rules: &TableRules) -> Result<(), ActionError> {
+        self.can_surrender(rules)?;
+        self.hands[0].state = HandState::Surrendered;
+        Ok(())
+    }
+
+    /// Whether the player's hand can be surrendered
+    pub fn can_surrender(&self, rules: &TableRules) -> Result<(), ActionError> {
+        if rules.surrender == Surrender::None {
+            Err(ActionError::SurrenderNotAllowed)
+        } else if self.hands[0].is_surrendered() {
+            Err(ActionError::AlreadySurrendered)
+        } else if self.hands[0].has_blackjack() {
+            Err(ActionError::Blackjack)
+        } else if self.hands[0].cards().len() != 2 {
+            Err(ActionError::AlreadyHit)
+        } else if self.hands.get(1).is_some() {
+            Err(ActionError::AlreadySplit)
         } else {
-            // If the player has splitted, the money earned
-            // by each winning hand should be the half,
-            // since when player splitted, bet got doubled,
-            // and each half of the bet represents one hand
-            self.actual_money += self.bet / 2;
-            if self.hands[hand_index].has_blackjack() {
-                self.actual_money += self.bet / 4;
-            }
+            Ok(())
         }
-        self.actual_money - money_before
     }
-    pub fn lose(&mut self) {
-        if self.hands.len() == 1 {
-            self.actual_money -= self.bet;
+
+    /// Splits the given hand if applicable, return the reason why not otherwise.
+    /// The new hand is placed right after it, with the same bet
+    pub fn split(
+        &mut self,
+        deck: &mut Deck,
+        hand_index: usize,
+        rules: &TableRules,
+    ) -> Result<(), ActionError> {
+        self.can_split(hand_index, rules)?;
+
+        let hand: &mut Hand = &mut self.hands[hand_index];
+        let cards: [Card; 2] = [
+            hand.remove_card().expect("Failed to split"),
+            deck.deal_card(),
+        ];
+        let mut new_hand = Hand::from_cards(&cards);
+        new_hand.wager.base = hand.wager.base;
+        new_hand.split = true;
+
+        hand.split = true;
+        hand.deal_card(deck);
+        self.hands.insert(hand_index + 1, new_hand);
+        Ok(())
+    }
+
+    /// Whether the given hand can be split
+    pub fn can_split(&self, hand_index: usize, rules: &TableRules) -> Result<(), ActionError> {
+        let hand: &Hand = &self.hands[hand_index];
+        let is_pair: bool = match rules.split_pairs {
+            SplitPairs::SameRank => hand.is_pair(),
+            SplitPairs::SameValue => hand.is_pair_of_value(),
+        };
+
+        if self.money_on_table() + hand.wager.base > self.actual_money {
+            Err(ActionError::InsufficientFunds)
+        } else if self.hands.len() >= usize::from(rules.max_split_hands) {
+            Err(ActionError::TooManyHands)
+        } else if hand.cards().len() != 2 {
+            Err(ActionError::AlreadyHit)
+        } else if !is_pair {
+            Err(ActionError::NotAPair(rules.split_pairs))
+        } else if self.hands.len() > 1 && hand.cards()[0].rank() == Rank::Ace && !rules.resplit_aces
+        {
+            Err(ActionError::ResplitAces)
         } else {
-            self.actual_money -= self.bet / 2;
+            Ok(())
         }
     }
+
+    /// Add the money won to the player's money
+    pub fn collect(&mut self, money: u32) {
+        self.actual_money += money;
+    }
+
+    /// Take the money lost from the player's money
+    pub fn pay(&mut self, money: u32) {
+        self.actual_money -= money;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+
+    fn player_with(cards: &[Card]) -> Player {
+        let mut player = Player::new(String::from("Player"), 100);
+        let mut hand = Hand::from_cards(cards);
+        hand.wager.base = 10;
+        player.hands = vec![hand];
+        player
+    }
+
+    fn blackjack() -> Player {
+        player_with(&[
+            Card::new(Rank::Ace, Suit::Hearts),
+            Card::new(Rank::King, Suit::Spades),
+        ])
+    }
+
+    #[test]
+    fn insured_hand_cannot_take_even_money() {
+        let mut player = blackjack();
+        // `insure` refuses blackjacks, but the wager can be set directly
+        player.hands[0].wager.insurance = 5;
+
+        assert_eq!(player.take_even_money(), Err(ActionError::AlreadyInsured));
+    }
+
+    #[test]
+    fn even_money_hand_cannot_be_insured() {
+        let mut player = blackjack();
+        player.take_even_money().unwrap();
+
+        assert_eq!(player.insure(5), Err(ActionError::EvenMoneyTaken));
+        assert_eq!(player.hands[0].wager.insurance, 0);
+    }
+
+    #[test]
+    fn blackjack_cannot_be_insured() {
+        let mut player = blackjack();
+
+        assert_eq!(player.insure(5), Err(ActionError::Blackjack));
+        assert_eq!(player.take_even_money(), Ok(()));
+    }
 }
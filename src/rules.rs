@@ -0,0 +1,352 @@
+use crate::args;
+use crate::hand::HandValue;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+/// When a player is allowed to give up half of the bet.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Surrender {
+    /// Surrender is not offered.
+    None,
+    /// Surrender is offered once the dealer has checked for blackjack.
+    Late,
+    /// Surrender is offered before the dealer checks for blackjack.
+    Early,
+}
+
+impl fmt::Display for Surrender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Surrender::None => "no surrender",
+            Surrender::Late => "late surrender",
+            Surrender::Early => "early surrender",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Surrender {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Surrender, String> {
+        match s.trim().to_lowercase().as_str() {
+            "none" | "no" => Ok(Surrender::None),
+            "late" => Ok(Surrender::Late),
+            "early" => Ok(Surrender::Early),
+            _ => Err(format!(
+                "'{}' is not a surrender type (none, late, early)",
+                s
+            )),
+        }
+    }
+}
+
+/// Which two cards make a pair that can be split.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SplitPairs {
+    /// Only cards of the same rank, e.g. two kings but not a king and a queen.
+    SameRank,
+    /// Any two cards of the same value, e.g. a king and a queen.
+    SameValue,
+}
+
+impl fmt::Display for SplitPairs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SplitPairs::SameRank => "split pairs of the same rank",
+            SplitPairs::SameValue => "split pairs of the same value",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SplitPairs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SplitPairs, String> {
+        match s.trim().to_lowercase().as_str() {
+            "rank" => Ok(SplitPairs::SameRank),
+            "value" => Ok(SplitPairs::SameValue),
+            _ => Err(format!("'{}' is not a way to split pairs (rank, value)", s)),
+        }
+    }
+}
+
+/// Which two-card totals can be doubled.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DoubleOn {
+    AnyTwo,
+    NineToEleven,
+    TenToEleven,
+}
+
+impl fmt::Display for DoubleOn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DoubleOn::AnyTwo => "double on any two cards",
+            DoubleOn::NineToEleven => "double on 9-11",
+            DoubleOn::TenToEleven => "double on 10-11",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for DoubleOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DoubleOn, String> {
+        match s.trim().to_lowercase().as_str() {
+            "any" => Ok(DoubleOn::AnyTwo),
+            "9-11" => Ok(DoubleOn::NineToEleven),
+            "10-11" => Ok(DoubleOn::TenToEleven),
+            _ => Err(format!("'{}' is not a doubling rule (any, 9-11, 10-11)", s)),
+        }
+    }
+}
+
+impl DoubleOn {
+    /// Whether a hand with the given total can be doubled.
+    pub fn allows(self, value: HandValue) -> bool {
+        match self {
+            DoubleOn::AnyTwo => true,
+            DoubleOn::NineToEleven => (9..=11).contains(&value.total()),
+            DoubleOn::TenToEleven => (10..=11).contains(&value.total()),
+        }
+    }
+}
+
+/// Payout of a blackjack, `won` for every `bet`, e.g. 3:2.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Payout {
+    pub won: u32,
+    pub bet: u32,
+}
+
+impl fmt::Display for Payout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.won, self.bet)
+    }
+}
+
+impl FromStr for Payout {
+    type Err = String;
+
+    /// Parses a payout written as `won:bet`, like `3:2` or `6:5`.
+    fn from_str(s: &str) -> Result<Payout, String> {
+        let invalid = || format!("'{}' is not a payout (like 3:2 or 6:5)", s);
+        let mut parts = s.trim().splitn(2, ':');
+        let won: u32 = parts
+            .next()
+            .and_then(|won| won.trim().parse().ok())
+            .ok_or_else(invalid)?;
+        let bet: u32 = parts
+            .next()
+            .and_then(|bet| bet.trim().parse().ok())
+            .filter(|&bet| bet > 0)
+            .ok_or_else(invalid)?;
+        Ok(Payout { won, bet })
+    }
+}
+
+/// The rules of the table, shared by the players and the dealer.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TableRules {
+    /// Number of decks in the shoe.
+    pub n_decks: u8,
+    /// Whether the dealer hits a soft 17 (H17) or stands on every 17 (S17).
+    pub dealer_hits_soft_17: bool,
+    /// Whether the dealer takes a hole card and checks it for blackjack before the players
+    /// play (American style). Otherwise the dealer takes the second card after the players
+    /// (European no hole card, ENHC), so doubled and split bets are lost to a blackjack.
+    pub dealer_peeks: bool,
+    pub blackjack_payout: Payout,
+    pub double_on: DoubleOn,
+    /// Whether a hand can be doubled after splitting (DAS).
+    pub double_after_split: bool,
+    /// Whether only cards of the same rank or any two cards of the same value can be split.
+    pub split_pairs: SplitPairs,
+    /// Maximum number of hands a player can get by splitting.
+    pub max_split_hands: u8,
+    /// Whether a pair of aces can be split again.
+    pub resplit_aces: bool,
+    /// Whether split aces can take more than one card each.
+    pub hit_split_aces: bool,
+    pub surrender: Surrender,
+    pub min_bet: u32,
+    pub max_bet: u32,
+}
+
+impl Default for TableRules {
+    fn default() -> TableRules {
+        TableRules {
+            n_decks: 6,
+            dealer_hits_soft_17: false,
+            dealer_peeks: true,
+            blackjack_payout: Payout { won: 3, bet: 2 },
+            double_on: DoubleOn::AnyTwo,
+            double_after_split: true,
+            split_pairs: SplitPairs::SameRank,
+            max_split_hands: 4,
+            resplit_aces: false,
+            hit_split_aces: false,
+            surrender: Surrender::Late,
+            min_bet: 1,
+            max_bet: u32::MAX,
+        }
+    }
+}
+
+impl fmt::Display for TableRules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} decks, dealer {}, {}, blackjack pays {}, {}, {}, {}, {}",
+            self.n_decks,
+            if self.dealer_hits_soft_17 {
+                "hits soft 17 (H17)"
+            } else {
+                "stands on soft 17 (S17)"
+            },
+            if self.dealer_peeks {
+                "dealer peeks for blackjack"
+            } else {
+                "no hole card (ENHC)"
+            },
+            self.blackjack_payout,
+            self.double_on,
+            if self.double_after_split {
+                "double after split"
+            } else {
+                "no double after split"
+            },
+            self.split_pairs,
+            self.surrender
+        )
+    }
+}
+
+impl TableRules {
+    /// Reads the rules from command line options like `--decks 8`, `--h17` or
+    /// `--surrender early`, keeping the default of every rule not given.
+    /// Returns an error message if an option has an invalid value.
+    pub fn from_args(args: &[String]) -> Result<TableRules, String> {
+        let mut rules = TableRules {
+            dealer_hits_soft_17: args::flag(args, "--h17"),
+            dealer_peeks: !args::flag(args, "--enhc"),
+            double_after_split: !args::flag(args, "--no-das"),
+            resplit_aces: args::flag(args, "--resplit-aces"),
+            hit_split_aces: args::flag(args, "--hit-split-aces"),
+            ..TableRules::default()
+        };
+        if let Some(n_decks) = args::option(args, "--decks")? {
+            if n_decks == 0 {
+                return Err(String::from(
+                    "Invalid value for --decks: there must be at least one deck",
+                ));
+            }
+            rules.n_decks = n_decks;
+        }
+        if let Some(payout) = args::option(args, "--payout")? {
+            rules.blackjack_payout = payout;
+        }
+        if let Some(surrender) = args::option(args, "--surrender")? {
+            rules.surrender = surrender;
+        }
+        if let Some(double_on) = args::option(args, "--double-on")? {
+            rules.double_on = double_on;
+        }
+        if let Some(split_pairs) = args::option(args, "--split-by")? {
+            rules.split_pairs = split_pairs;
+        }
+        if let Some(max_split_hands) = args::option(args, "--split-hands")? {
+            rules.max_split_hands = max_split_hands;
+        }
+        if let Some(min_bet) = args::option(args, "--min-bet")? {
+            rules.min_bet = min_bet;
+        }
+        if let Some(max_bet) = args::option(args, "--max-bet")? {
+            rules.max_bet = max_bet;
+        }
+        if rules.min_bet > rules.max_bet {
+            return Err(format!(
+                "The minimum bet ({}) cannot be greater than the maximum bet ({})",
+                rules.min_bet, rules.max_bet
+            ));
+        }
+        Ok(rules)
+    }
+
+    /// Whether the dealer has to draw another card with the given total.
+    pub fn dealer_hits(&self, value: HandValue) -> bool {
+        match value {
+            HandValue::Soft(17) => self.dealer_hits_soft_17,
+            value => value.total() < 17,
+        }
+    }
+
+    /// Money won by a blackjack given the `bet`, rounded down.
+    pub fn blackjack_winnings(&self, bet: u32) -> u32 {
+        let payout: Payout = self.blackjack_payout;
+        let winnings: u64 = u64::from(bet) * u64::from(payout.won) / u64::from(payout.bet);
+        u32::try_from(winnings).unwrap_or(u32::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn payout_needs_a_positive_bet() {
+        assert_eq!("6:5".parse(), Ok(Payout { won: 6, bet: 5 }));
+        assert!("6:0".parse::<Payout>().is_err());
+        assert!("6".parse::<Payout>().is_err());
+        assert!("a:b".parse::<Payout>().is_err());
+    }
+
+    #[test]
+    fn blackjack_winnings_do_not_overflow() {
+        let rules = TableRules::default();
+
+        assert_eq!(rules.blackjack_winnings(15), 22);
+        assert_eq!(rules.blackjack_winnings(2_000_000_000), 3_000_000_000);
+    }
+
+    #[test]
+    fn rules_from_args_keep_the_defaults_of_missing_options() {
+        let rules: TableRules =
+            TableRules::from_args(&args("game --decks 2 --h17 --payout 6:5 --surrender none"))
+                .unwrap();
+
+        assert_eq!(
+            rules,
+            TableRules {
+                n_decks: 2,
+                dealer_hits_soft_17: true,
+                blackjack_payout: Payout { won: 6, bet: 5 },
+                surrender: Surrender::None,
+                ..TableRules::default()
+            }
+        );
+        assert_eq!(
+            TableRules::from_args(&args("game")),
+            Ok(TableRules::default())
+        );
+    }
+
+    #[test]
+    fn rules_from_args_reject_invalid_values() {
+        assert!(TableRules::from_args(&args("game --decks 0")).is_err());
+        assert!(TableRules::from_args(&args("game --payout 3:0")).is_err());
+        assert!(TableRules::from_args(&args("game --double-on 8-11")).is_err());
+        assert!(TableRules::from_args(&args("game --split-hands")).is_err());
+        assert!(TableRules::from_args(&args("game --min-bet 100 --max-bet 10")).is_err());
+        assert!(TableRules::from_args(&args("game --min-bet 10 --max-bet 10")).is_ok());
+    }
+}
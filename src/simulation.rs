@@ -0,0 +1,240 @@
+use crate::chart::Chart;
+use crate::deck::{Card, Deck};
+use crate::game::{Action, Event, Game, Outcome, Phase};
+use crate::hand::Hand;
+use crate::rules::TableRules;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The bet of the simulated player, big enough for 3:2 and 6:5 payouts
+/// and surrenders to be paid in whole units.
+pub const BET: u32 = 10;
+
+/// Money the simulated player sits with, so it never runs out.
+const BANKROLL: u32 = u32::MAX / 2;
+
+/// Rounds played from each seed when the simulation is split across threads.
+/// The shards do not depend on the number of threads, so neither do the results.
+pub const SHARD_ROUNDS: u64 = 100_000;
+
+/// Results of simulated rounds, in money units of a `BET` per round.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct SimulationStats {
+    pub rounds: u64,
+    /// Hands played, counting every hand of a split.
+    pub hands: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub pushes: u64,
+    pub blackjacks: u64,
+    /// Money won by the player, negative if lost.
+    pub net: i64,
+    /// Sum of the squared result of every round, to work out the standard deviation.
+    pub net_squares: f64,
+}
+
+impl SimulationStats {
+    /// Expected loss of the player, as a fraction of the initial bet.
+    pub fn house_edge(&self) -> f64 {
+        -self.mean()
+    }
+
+    /// Expected result of a round, as a fraction of the initial bet.
+    pub fn mean(&self) -> f64 {
+        self.net as f64 / self.rounds as f64 / f64::from(BET)
+    }
+
+    /// Standard deviation of the result of a round, as a fraction of the initial bet.
+    pub fn std_dev(&self) -> f64 {
+        let mean_square: f64 = self.net_squares / self.rounds as f64 / f64::from(BET * BET);
+        (mean_square - self.mean() * self.mean()).sqrt()
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.hands as f64
+    }
+
+    pub fn loss_rate(&self) -> f64 {
+        self.losses as f64 / self.hands as f64
+    }
+
+    pub fn push_rate(&self) -> f64 {
+        self.pushes as f64 / self.hands as f64
+    }
+
+    /// Blackjacks dealt to the player per round.
+    pub fn blackjack_rate(&self) -> f64 {
+        self.blackjacks as f64 / self.rounds as f64
+    }
+
+    /// Standard error of the house edge.
+    pub fn std_error(&self) -> f64 {
+        self.std_dev() / (self.rounds as f64).sqrt()
+    }
+
+    /// Interval around the house edge within `z` standard errors,
+    /// e.g. `1.96` for a 95% confidence interval.
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        let margin: f64 = z * self.std_error();
+        (self.house_edge() - margin, self.house_edge() + margin)
+    }
+
+    /// Adds up the results of `other` simulated rounds.
+    pub fn merge(&mut self, other: &SimulationStats) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.pushes += other.pushes;
+        self.blackjacks += other.blackjacks;
+        self.net += other.net;
+        self.net_squares += other.net_squares;
+    }
+}
+
+/// Same as `simulate`, but split in shards of `SHARD_ROUNDS` rounds played by `threads` threads.
+/// The seed of every shard is drawn from `seed` and the shards are merged in order,
+/// so the results only depend on `seed`, whatever the number of threads.
+pub fn simulate_parallel(
+    rules: &TableRules,
+    chart: &Chart,
+    rounds: u64,
+    seed: u64,
+    penetration: f64,
+    threads: usize,
+) -> SimulationStats {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let shards: Vec<(u64, u64)> = (0..rounds)
+        .step_by(SHARD_ROUNDS as usize)
+        .map(|start| ((rounds - start).min(SHARD_ROUNDS), rng.gen()))
+        .collect();
+    let results: Mutex<Vec<Option<SimulationStats>>> = Mutex::new(vec![None; shards.len()]);
+    let next_shard = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let shard: usize = next_shard.fetch_add(1, Ordering::Relaxed);
+                let (shard_rounds, shard_seed) = match shards.get(shard) {
+                    Some(&shard) => shard,
+                    None => break,
+                };
+                let stats: SimulationStats =
+                    simulate(rules, chart, shard_rounds, shard_seed, penetration);
+                results.lock().expect("A simulation thread panicked")[shard] = Some(stats);
+            });
+        }
+    });
+
+    let mut stats = SimulationStats::default();
+    for shard in results.into_inner().expect("A simulation thread panicked") {
+        stats.merge(&shard.expect("Every shard was simulated"));
+    }
+    stats
+}
+
+/// Plays `rounds` rounds with a single player following the `chart`, on a shoe shuffled
+/// from `seed` and cut at `penetration`. The player never takes insurance nor even money.
+/// The table limits of the `rules` must allow a bet of `BET`.
+pub fn simulate(
+    rules: &TableRules,
+    chart: &Chart,
+    rounds: u64,
+    seed: u64,
+    penetration: f64,
+) -> SimulationStats {
+    let mut deck = Deck::from_seed(rules.n_decks, seed);
+    deck.set_penetration(penetration);
+    let mut game = Game::new(rules.clone(), deck);
+    game.add_player(String::from("Simulator"), BANKROLL);
+    let mut stats = SimulationStats::default();
+
+    for _ in 0..rounds {
+        let money_before: u32 = game.players()[0].actual_money;
+        game.place_bet(0, BET)
+            .expect("The bet is within the table limits");
+        game.deal().expect("The player has bet");
+        if game.players()[0].hands[0].has_blackjack() {
+            stats.blackjacks += 1;
+        }
+        play_round(&mut game, chart);
+
+        for event in game.take_events() {
+            if let Event::Settled { outcome, .. } = event {
+                stats.hands += 1;
+                match outcome {
+                    Outcome::Won(_) => stats.wins += 1,
+                    Outcome::Lost(_) => stats.losses += 1,
+                    Outcome::Tied => stats.pushes += 1,
+                }
+            }
+        }
+        let net: i64 = i64::from(game.players()[0].actual_money) - i64::from(money_before);
+        stats.rounds += 1;
+        stats.net += net;
+        stats.net_squares += (net * net) as f64;
+        game.next_round();
+    }
+    stats
+}
+
+/// Makes every decision of the dealt round following the `chart`.
+fn play_round(game: &mut Game, chart: &Chart) {
+    let upcard: Card = game.dealer_hand().cards()[0];
+
+    if game.phase() == Phase::Surrender {
+        let hand: &Hand = &game.players()[0].hands[0];
+        let actions: [Action; 3] = [Action::Hit, Action::Stand, Action::Surrender];
+        if chart.recommend(hand, upcard, &actions) == Action::Surrender {
+            // A blackjack cannot be surrendered, which is fine to ignore here
+            let _ = game.surrender(0);
+        }
+        game.close_surrender().expect("Early surrender was offered");
+    }
+    if game.phase() == Phase::Insurance {
+        game.close_insurance().expect("Insurance was offered");
+    }
+    while let Some((player, hand)) = game.turn() {
+        let actions: Vec<Action> = game.available_actions(player, hand);
+        let action: Action = chart.recommend(&game.players()[player].hands[hand], upcard, &actions);
+        game.act(action).expect("The action is available");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_results() {
+        let rules = TableRules::default();
+        let chart: Chart = Chart::basic(&rules);
+
+        assert_eq!(
+            simulate(&rules, &chart, 10_000, 5, 0.75),
+            simulate(&rules, &chart, 10_000, 5, 0.75)
+        );
+    }
+
+    #[test]
+    fn results_do_not_depend_on_the_number_of_threads() {
+        let rules = TableRules::default();
+        let chart: Chart = Chart::basic(&rules);
+        // A full shard and a shorter one
+        let rounds: u64 = SHARD_ROUNDS + 1_000;
+
+        let single: SimulationStats = simulate_parallel(&rules, &chart, rounds, 9, 0.75, 1);
+        assert_eq!(single.rounds, rounds);
+        for &threads in [2, 4].iter() {
+            assert_eq!(
+                simulate_parallel(&rules, &chart, rounds, 9, 0.75, threads),
+                single
+            );
+        }
+    }
+}
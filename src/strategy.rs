@@ -0,0 +1,344 @@
+use crate::deck::Rank;
+use crate::game::Action;
+use crate::rules::{Surrender, TableRules};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A play of a strategy chart, which may depend on the actions the hand can take.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Play {
+    Hit,
+    Stand,
+    /// Double if allowed, otherwise hit.
+    DoubleOrHit,
+    /// Double if allowed, otherwise stand.
+    DoubleOrStand,
+    Split,
+    /// Surrender if allowed, otherwise hit.
+    SurrenderOrHit,
+    /// Surrender if allowed, otherwise stand.
+    SurrenderOrStand,
+    /// Surrender if allowed, otherwise split.
+    SurrenderOrSplit,
+}
+
+impl fmt::Display for Play {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            Play::Hit => "H",
+            Play::Stand => "S",
+            Play::DoubleOrHit => "D",
+            Play::DoubleOrStand => "Ds",
+            Play::Split => "P",
+            Play::SurrenderOrHit => "Rh",
+            Play::SurrenderOrStand => "Rs",
+            Play::SurrenderOrSplit => "Rp",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+impl FromStr for Play {
+    type Err = String;
+
+    /// Parses the usual chart codes (`"H"`, `"S"`, `"D"`, `"Ds"`, `"P"`, `"Rh"`, `"Rs"`, `"Rp"`),
+    /// ignoring case and surrounding whitespace.
+    fn from_str(s: &str) -> Result<Play, String> {
+        match s.trim().to_lowercase().as_str() {
+            "h" => Ok(Play::Hit),
+            "s" => Ok(Play::Stand),
+            "d" | "dh" => Ok(Play::DoubleOrHit),
+            "ds" => Ok(Play::DoubleOrStand),
+            "p" => Ok(Play::Split),
+            "rh" => Ok(Play::SurrenderOrHit),
+            "rs" => Ok(Play::SurrenderOrStand),
+            "rp" => Ok(Play::SurrenderOrSplit),
+            _ => Err(format!(
+                "'{}' is not a play (H, S, D, Ds, P, Rh, Rs, Rp)",
+                s
+            )),
+        }
+    }
+}
+
+impl Play {
+    /// The action to take given the `actions` the hand can take right now.
+    pub fn resolve(self, actions: &[Action]) -> Action {
+        let allowed = |action: Action| actions.contains(&action);
+        let action: Action = match self {
+            Play::Hit => Action::Hit,
+            Play::Stand => Action::Stand,
+            Play::DoubleOrHit if allowed(Action::Double) => Action::Double,
+            Play::DoubleOrHit => Action::Hit,
+            Play::DoubleOrStand if allowed(Action::Double) => Action::Double,
+            Play::DoubleOrStand => Action::Stand,
+            Play::Split if allowed(Action::Split) => Action::Split,
+            Play::Split => Action::Hit,
+            Play::SurrenderOrHit if allowed(Action::Surrender) => Action::Surrender,
+            Play::SurrenderOrHit => Action::Hit,
+            Play::SurrenderOrStand if allowed(Action::Surrender) => Action::Surrender,
+            Play::SurrenderOrStand => Action::Stand,
+            Play::SurrenderOrSplit if allowed(Action::Surrender) => Action::Surrender,
+            Play::SurrenderOrSplit if allowed(Action::Split) => Action::Split,
+            Play::SurrenderOrSplit => Action::Hit,
+        };
+
+        // Split aces that cannot take more cards can only stand
+        if action == Action::Hit && !allowed(Action::Hit) {
+            Action::Stand
+        } else {
+            action
+        }
+    }
+}
+
+/// The play for a pair of `rank`, or `None` if it should be played as a total.
+pub fn pair_play(rank: Rank, upcard: Rank, rules: &TableRules) -> Option<Play> {
+    let dealer: u8 = upcard.value();
+    let das: bool = rules.double_after_split;
+    let surrender: Surrender = rules.surrender;
+
+    let split: bool = match rank.value() {
+        11 => true,
+        10 | 5 => false,
+        9 => (2..=9).contains(&dealer) && dealer != 7,
+        8 => {
+            if dealer == 11 && surrender != Surrender::None && rules.dealer_hits_soft_17 {
+                return Some(Play::SurrenderOrSplit);
+            }
+            if surrender == Surrender::Early && (dealer == 10 || dealer == 11) {
+                return Some(Play::SurrenderOrSplit);
+            }
+            true
+        }
+        7 => {
+            if surrender == Surrender::Early && (dealer == 10 || dealer == 11) {
+                return Some(Play::SurrenderOrHit);
+            }
+            (2..=7).contains(&dealer)
+        }
+        6 => (3..=6).contains(&dealer) || (das && dealer == 2),
+        4 => das && (dealer == 5 || dealer == 6),
+        _ => (4..=7).contains(&dealer) || (das && (dealer == 2 || dealer == 3)),
+    };
+    if split {
+        Some(Play::Split)
+    } else {
+        None
+    }
+}
+
+/// The play for a soft `total` against the dealer's `upcard`.
+pub fn soft_play(total: u8, upcard: Rank, rules: &TableRules) -> Play {
+    let dealer: u8 = upcard.value();
+    let h17: bool = rules.dealer_hits_soft_17;
+    match total {
+        20..=21 => Play::Stand,
+        19 if h17 && dealer == 6 => Play::DoubleOrStand,
+        19 => Play::Stand,
+        18 if (3..=6).contains(&dealer) || (h17 && dealer == 2) => Play::DoubleOrStand,
+        18 if dealer <= 8 => Play::Stand,
+        18 => Play::Hit,
+        17 if (3..=6).contains(&dealer) => Play::DoubleOrHit,
+        15..=16 if (4..=6).contains(&dealer) => Play::DoubleOrHit,
+        13..=14 if (5..=6).contains(&dealer) => Play::DoubleOrHit,
+        _ => Play::Hit,
+    }
+}
+
+/// The play for a hard `total` against the dealer's `upcard`, surrendering if it is worth it.
+pub fn hard_play(total: u8, upcard: Rank, rules: &TableRules) -> Play {
+    let dealer: u8 = upcard.value();
+    if let Some(play) = surrender_play(total, dealer, rules) {
+        return play;
+    }
+    let few_decks: bool = rules.n_decks <= 2;
+    match total {
+        17..=21 => Play::Stand,
+        13..=16 if dealer <= 6 => Play::Stand,
+        12 if (4..=6).contains(&dealer) => Play::Stand,
+        11 if dealer != 11 || rules.dealer_hits_soft_17 || few_decks => Play::DoubleOrHit,
+        10 if dealer <= 9 => Play::DoubleOrHit,
+        9 if (3..=6).contains(&dealer) || (few_decks && dealer == 2) => Play::DoubleOrHit,
+        _ => Play::Hit,
+    }
+}
+
+/// The hard totals worth surrendering, if the table allows it.
+fn surrender_play(total: u8, dealer: u8, rules: &TableRules) -> Option<Play> {
+    let h17: bool = rules.dealer_hits_soft_17;
+    let surrender: bool = match rules.surrender {
+        Surrender::None => false,
+        Surrender::Late => match dealer {
+            11 => total == 16 || (h17 && (total == 15 || total == 17)),
+            10 => total == 15 || total == 16,
+            9 => total == 16,
+            _ => false,
+        },
+        Surrender::Early => match dealer {
+            11 => (5..=7).contains(&total) || (12..=17).contains(&total),
+            10 => (14..=16).contains(&total),
+            9 => total == 16,
+            _ => false,
+        },
+    };
+    if !surrender {
+        None
+    } else if total >= 17 {
+        Some(Play::SurrenderOrStand)
+    } else {
+        Some(Play::SurrenderOrHit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Six decks, S17, late surrender and DAS, changed by `change`.
+    fn rules(change: fn(&mut TableRules)) -> TableRules {
+        let mut rules = TableRules::default();
+        change(&mut rules);
+        rules
+    }
+
+    fn h17(rules: &mut TableRules) {
+        rules.dealer_hits_soft_17 = true;
+    }
+
+    #[test]
+    fn hard_totals_depend_on_the_rules() {
+        let cases: [(u8, Rank, TableRules, Play); 8] = [
+            (11, Rank::Ace, rules(|_| {}), Play::Hit),
+            (11, Rank::Ace, rules(h17), Play::DoubleOrHit),
+            (
+                11,
+                Rank::Ace,
+                rules(|rules| rules.n_decks = 2),
+                Play::DoubleOrHit,
+            ),
+            (9, Rank::Two, rules(|_| {}), Play::Hit),
+            (
+                9,
+                Rank::Two,
+                rules(|rules| rules.n_decks = 1),
+                Play::DoubleOrHit,
+            ),
+            (16, Rank::Ten, rules(|_| {}), Play::SurrenderOrHit),
+            (
+                16,
+                Rank::Ten,
+                rules(|rules| rules.surrender = Surrender::None),
+                Play::Hit,
+            ),
+            (17, Rank::Ace, rules(h17), Play::SurrenderOrStand),
+        ];
+        for (total, upcard, rules, play) in cases.iter() {
+            assert_eq!(
+                hard_play(*total, *upcard, rules),
+                *play,
+                "hard {} against {} with {}",
+                total,
+                upcard,
+                rules
+            );
+        }
+    }
+
+    #[test]
+    fn soft_totals_depend_on_the_rules() {
+        let cases: [(u8, Rank, TableRules, Play); 4] = [
+            (18, Rank::Two, rules(|_| {}), Play::Stand),
+            (18, Rank::Two, rules(h17), Play::DoubleOrStand),
+            (19, Rank::Six, rules(|_| {}), Play::Stand),
+            (19, Rank::Six, rules(h17), Play::DoubleOrStand),
+        ];
+        for (total, upcard, rules, play) in cases.iter() {
+            assert_eq!(
+                soft_play(*total, *upcard, rules),
+                *play,
+                "soft {} against {} with {}",
+                total,
+                upcard,
+                rules
+            );
+        }
+    }
+
+    #[test]
+    fn pairs_depend_on_the_rules() {
+        let cases: [(Rank, Rank, TableRules, Option<Play>); 8] = [
+            (Rank::Eight, Rank::Ace, rules(|_| {}), Some(Play::Split)),
+            (
+                Rank::Eight,
+                Rank::Ace,
+                rules(h17),
+                Some(Play::SurrenderOrSplit),
+            ),
+            (
+                Rank::Eight,
+                Rank::Ten,
+                rules(|rules| rules.surrender = Surrender::Early),
+                Some(Play::SurrenderOrSplit),
+            ),
+            (Rank::Four, Rank::Five, rules(|_| {}), Some(Play::Split)),
+            (
+                Rank::Four,
+                Rank::Five,
+                rules(|rules| rules.double_after_split = false),
+                None,
+            ),
+            (Rank::Six, Rank::Two, rules(|_| {}), Some(Play::Split)),
+            (
+                Rank::Six,
+                Rank::Two,
+                rules(|rules| rules.double_after_split = false),
+                None,
+            ),
+            (Rank::Ten, Rank::Six, rules(|_| {}), None),
+        ];
+        for (pair, upcard, rules, play) in cases.iter() {
+            assert_eq!(
+                pair_play(*pair, *upcard, rules),
+                *play,
+                "pair of {} against {} with {}",
+                pair,
+                upcard,
+                rules
+            );
+        }
+        // Without DAS, a pair of fours is played as a hard 8
+        assert_eq!(
+            hard_play(
+                8,
+                Rank::Five,
+                &rules(|rules| rules.double_after_split = false)
+            ),
+            Play::Hit
+        );
+    }
+
+    #[test]
+    fn plays_resolve_to_the_available_actions() {
+        let all: [Action; 5] = [
+            Action::Hit,
+            Action::Stand,
+            Action::Split,
+            Action::Double,
+            Action::Surrender,
+        ];
+        let after_hit: [Action; 2] = [Action::Hit, Action::Stand];
+
+        assert_eq!(Play::DoubleOrStand.resolve(&all), Action::Double);
+        assert_eq!(Play::DoubleOrStand.resolve(&after_hit), Action::Stand);
+        assert_eq!(Play::SurrenderOrSplit.resolve(&all), Action::Surrender);
+        assert_eq!(
+            Play::SurrenderOrSplit.resolve(&[Action::Hit, Action::Stand, Action::Split]),
+            Action::Split
+        );
+        assert_eq!(Play::Split.resolve(&after_hit), Action::Hit);
+        // Split aces that cannot take more cards
+        assert_eq!(Play::DoubleOrHit.resolve(&[Action::Stand]), Action::Stand);
+    }
+}
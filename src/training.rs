@@ -0,0 +1,87 @@
+use crate::game::Action;
+use crate::hand::Hand;
+
+use std::fmt;
+
+/// The kind of decision a hand faces, as grouped in strategy charts.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HandCategory {
+    Hard,
+    Soft,
+    /// A pair that can be split.
+    Pair,
+}
+
+impl fmt::Display for HandCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            HandCategory::Hard => "hard totals",
+            HandCategory::Soft => "soft totals",
+            HandCategory::Pair => "pairs",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl HandCategory {
+    pub const ALL: [HandCategory; 3] = [HandCategory::Hard, HandCategory::Soft, HandCategory::Pair];
+
+    /// The category of the `hand`, given the `actions` it can take.
+    pub fn of(hand: &Hand, actions: &[Action]) -> HandCategory {
+        if hand.is_pair() && actions.contains(&Action::Split) {
+            HandCategory::Pair
+        } else if hand.is_soft() {
+            HandCategory::Soft
+        } else {
+            HandCategory::Hard
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            HandCategory::Hard => 0,
+            HandCategory::Soft => 1,
+            HandCategory::Pair => 2,
+        }
+    }
+}
+
+/// Decisions and mistakes of a training session, by `HandCategory`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TrainingStats {
+    decisions: [u32; 3],
+    mistakes: [u32; 3],
+}
+
+impl TrainingStats {
+    pub fn new() -> TrainingStats {
+        TrainingStats::default()
+    }
+
+    /// Records the `action` taken on a hand of the given `category` against the `recommended`
+    /// one, returning whether it was a mistake.
+    pub fn record(&mut self, category: HandCategory, action: Action, recommended: Action) -> bool {
+        let mistake: bool = action != recommended;
+        self.decisions[category.index()] += 1;
+        if mistake {
+            self.mistakes[category.index()] += 1;
+        }
+        mistake
+    }
+
+    pub fn decisions(&self, category: HandCategory) -> u32 {
+        self.decisions[category.index()]
+    }
+
+    pub fn mistakes(&self, category: HandCategory) -> u32 {
+        self.mistakes[category.index()]
+    }
+
+    /// Fraction of the decisions of the `category` that were mistakes, `None` if there were none.
+    pub fn error_rate(&self, category: HandCategory) -> Option<f64> {
+        match self.decisions(category) {
+            0 => None,
+            decisions => Some(f64::from(self.mistakes(category)) / f64::from(decisions)),
+        }
+    }
+}